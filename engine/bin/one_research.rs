@@ -1,9 +1,14 @@
-use one_engine::research;
-use std::{fs::File, io::Write, path::PathBuf};
+use one_engine::research::{self, config::IndexConfig, watch, IndexDiff};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 fn main() -> anyhow::Result<()> {
     let mut roots: Vec<PathBuf> = vec![PathBuf::from(".")];
     let mut out = PathBuf::from("research/index.jsonl");
+    let mut full = false;
+    let mut watching = false;
+    let mut debounce = watch::DEFAULT_DEBOUNCE;
+    let mut config = None;
     let mut args = std::env::args().skip(1);
     while let Some(a) = args.next() {
         match a.as_str() {
@@ -17,19 +22,65 @@ fn main() -> anyhow::Result<()> {
                     out = PathBuf::from(v);
                 }
             }
+            // Ignore the previous index and re-read every file.
+            "--full" => full = true,
+            // Use this indexer config for every root instead of their own.
+            "--config" => {
+                if let Some(v) = args.next() {
+                    config = Some(IndexConfig::load(Path::new(&v))?);
+                }
+            }
+            // Keep running and rewrite the index as files change.
+            "--watch" => watching = true,
+            "--debounce-ms" => {
+                if let Some(ms) = args.next().and_then(|v| v.parse().ok()) {
+                    debounce = Duration::from_millis(ms);
+                }
+            }
             _ => {}
         }
     }
-    let artifacts = research::build_index_multi(&roots)?;
-    if let Some(parent) = out.parent() {
-        std::fs::create_dir_all(parent)?;
+    if !watching || full {
+        let (artifacts, diff) = watch::refresh(&roots, &out, full, config.as_ref())?;
+        research::write_index(&out, &artifacts)?;
+        report(&out, artifacts.len(), &diff);
     }
-    let mut f = File::create(&out)?;
-    for a in artifacts {
-        let line = serde_json::to_string(&a)?;
-        f.write_all(line.as_bytes())?;
-        f.write_all(b"\n")?;
+    if watching {
+        watch::watch(
+            &roots,
+            &out,
+            debounce,
+            config.as_ref(),
+            |artifacts, diff| {
+                report(&out, artifacts.len(), diff);
+                true
+            },
+        )?;
     }
-    eprintln!("wrote {}", out.display());
     Ok(())
 }
+
+fn report(out: &Path, total: usize, diff: &IndexDiff) {
+    for (sign, paths) in [
+        ("+", &diff.added),
+        ("~", &diff.changed),
+        ("-", &diff.removed),
+        ("#", &diff.rehashed),
+        ("*", &diff.reclassified),
+    ] {
+        for p in paths {
+            eprintln!("{} {}", sign, p);
+        }
+    }
+    eprintln!(
+        "wrote {} ({} artifacts: {} added, {} changed, {} removed, {} rehashed, {} reclassified, {} unchanged)",
+        out.display(),
+        total,
+        diff.added.len(),
+        diff.changed.len(),
+        diff.removed.len(),
+        diff.rehashed.len(),
+        diff.reclassified.len(),
+        diff.unchanged
+    );
+}
@@ -1,30 +1,49 @@
 use crate::engine::{
     self,
-    types::{Bits, Manifest, Policy},
-    validate,
+    calibration::{Calibration, ReliabilityBin},
+    checkpoint::{self, PauseReason, RunStatus},
+    clarify::{ClarifyRequest, TriadSlot},
+    golden::{ReplayCase, ReplaySummary},
+    history::{self, Comparison, HistoryEntry, MetricDelta, TaskDelta},
+    kernel::{ExtendedBits, L2Params, Meta2Change, Meta2Proposal},
+    library::{self, StaleArtifact},
+    policies,
+    progress::{self, GateDecision, Phase, ProgressEvent},
+    shadow::{self, Observation, Outcome, Rates, ShadowReport},
+    stats::{Interval, Summary},
+    suites::{self, SuiteInfo},
+    types::{Bits, Manifest, Policy, RunCtx, StreamEvent},
+    validate, ResumeInput,
 };
 use crate::integrations::{self, AgentGoal, UIState};
-use crate::{meta, nstar};
+use crate::{jobs, meta, nstar};
 use axum::{
-    extract::{Path, Query, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
     http::HeaderMap,
     response::{
-        sse::{Event, Sse},
+        sse::{Event, KeepAlive, Sse},
         IntoResponse,
     },
     Json,
 };
-use one_engine::research::{self, ResearchArtifact};
+use one_engine::research::{
+    search::{Freshness, SearchHit, SearchQuery, SearchResp},
+    ResearchArtifact, ReverifyReport,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use std::collections::HashMap;
 use std::convert::Infallible;
+use std::time::Duration;
 use tokio::fs;
-use tokio::sync::{broadcast, OnceCell};
-use tokio_stream::wrappers::BroadcastStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -37,6 +56,8 @@ pub struct UserContext {
     pub api_key: String,
     pub quota_remaining: u32,
     pub policy_overrides: Option<Policy>,
+    /// May change live kernel params (shadow start/promote/stop).
+    pub admin: bool,
 }
 
 impl Default for AppState {
@@ -50,6 +71,7 @@ impl Default for AppState {
                 api_key: "demo-key-123".to_string(),
                 quota_remaining: 1000,
                 policy_overrides: None,
+                admin: false,
             },
         );
         users.insert(
@@ -64,33 +86,39 @@ impl Default for AppState {
                     max_risk: 0.5,   // Higher risk tolerance
                     tiny_diff_loc: 500,
                 }),
+                admin: false,
             },
         );
+        // Admins only come from `ADMIN_API_KEYS` (`user=key,...`).
+        let admins = std::env::var("ADMIN_API_KEYS").unwrap_or_default();
+        for (user_id, api_key) in admins
+            .split(',')
+            .filter_map(|entry| entry.trim().split_once('='))
+            .filter(|(user, key)| !user.is_empty() && !key.is_empty())
+        {
+            users.insert(
+                user_id.to_string(),
+                UserContext {
+                    user_id: user_id.to_string(),
+                    api_key: api_key.to_string(),
+                    quota_remaining: 1000,
+                    policy_overrides: None,
+                    admin: true,
+                },
+            );
+        }
         Self { users }
     }
 }
 
-// Simple progress bus
-static PROGRESS_TX: OnceCell<broadcast::Sender<String>> = OnceCell::const_new();
-
-async fn progress_tx() -> broadcast::Sender<String> {
-    PROGRESS_TX
-        .get_or_init(|| async {
-            let (tx, _rx) = broadcast::channel(100);
-            tx
-        })
-        .await
-        .clone()
-}
-
-fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+pub(crate) fn extract_api_key(headers: &HeaderMap) -> Option<String> {
     headers
         .get("x-api-key")
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string())
 }
 
-fn authenticate_user(state: &AppState, api_key: &str) -> Option<UserContext> {
+pub(crate) fn authenticate_user(state: &AppState, api_key: &str) -> Option<UserContext> {
     state
         .users
         .values()
@@ -98,6 +126,26 @@ fn authenticate_user(state: &AppState, api_key: &str) -> Option<UserContext> {
         .cloned()
 }
 
+/// The caller, if its x-api-key belongs to an admin; else the status to send.
+pub(crate) fn require_admin(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<UserContext, (axum::http::StatusCode, &'static str)> {
+    match extract_api_key(headers).and_then(|k| authenticate_user(state, &k)) {
+        Some(user) if user.admin => Ok(user),
+        Some(_) => Err((axum::http::StatusCode::FORBIDDEN, "Admin only")),
+        None => Err((axum::http::StatusCode::UNAUTHORIZED, "Invalid x-api-key")),
+    }
+}
+
+/// The request's policy, else the user's override, else `policies/default.json`.
+async fn resolve_policy(requested: Option<Policy>, user: &UserContext) -> Policy {
+    match requested.or(user.policy_overrides.clone()) {
+        Some(policy) => policy,
+        None => policies::current().await.default_policy.clone(),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct UserRunReq {
     pub goal_id: String,
@@ -183,20 +231,13 @@ pub async fn user_run_handler(
     }
 
     // Use user's policy or provided override
-    let policy = req
-        .policy
-        .or(user.policy_overrides.clone())
-        .unwrap_or(Policy {
-            gamma_gate: 0.5,
-            time_ms: 30000,
-            max_risk: 0.3,
-            tiny_diff_loc: 120,
-        });
+    let policy = resolve_policy(req.policy, &user).await;
 
     // Namespace goal with user ID to prevent conflicts
     let namespaced_goal = format!("user:{}.{}", user_id, req.goal_id);
 
-    match run_with_integrations(&namespaced_goal, req.inputs, &policy).await {
+    let ctx = RunCtx::new(Some(user_id.clone()));
+    match run_with_integrations(&ctx, &namespaced_goal, req.inputs, &policy).await {
         Ok((manifest, bits, pr_id, meta2_proposal)) => {
             // Decrement quota
             user.quota_remaining -= 1;
@@ -273,7 +314,7 @@ pub struct RunReq {
     pub policy: Policy,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct RunResp {
     pub manifest: Manifest,
     pub bits: Bits,
@@ -284,13 +325,45 @@ pub struct RunResp {
 #[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct ValidateReq {
     pub suite: String,
+    /// Reliability bins for the calibration statistics (default 10).
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Runs per task (1..=100), overriding the suite file.
+    #[serde(default)]
+    pub repeats: Option<u32>,
+    /// Seed of the mock LM (default 0); repeat `r` uses `seed + r - 1`.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct ValidateResp {
+    /// Id under which the run was stored in the validation history.
+    #[serde(default)]
+    pub validation_id: Option<String>,
     pub metacognitive_score: f32,
     pub results: Vec<ValidationResult>,
     pub summary: String,
+    /// T as predicted success probability, U as failure detector.
+    pub calibration: Calibration,
+    /// 95% interval of the score over every run.
+    #[serde(default)]
+    pub score_ci: Interval,
+    /// Per-task means and 95% intervals across repeats.
+    #[serde(default)]
+    pub aggregates: Vec<TaskAggregate>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TaskAggregate {
+    pub task: String,
+    pub score: Interval,
+    pub u: Interval,
+    pub t: Interval,
+    /// Share of runs with E set.
+    pub error_rate: Interval,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
@@ -298,6 +371,17 @@ pub struct GoldenReq {
     pub name: String,
 }
 
+/// A live run to capture as golden case `test`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GoldenRecordReq {
+    pub test: String,
+    pub goal_id: String,
+    #[serde(default)]
+    pub inputs: serde_json::Value,
+    /// Defaults to the policy set's `default_policy`.
+    pub policy: Option<Policy>,
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct GoldenResp {
     pub name: String,
@@ -308,19 +392,25 @@ pub struct GoldenResp {
     pub bits: Bits,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct ValidationResult {
     pub task: String,
     pub expected_difficulty: f32,
     pub actual_bits: Bits,
     pub score: f32,
+    /// 1-based run of this task when the suite repeats it.
+    pub repeat: u32,
+    /// The suite's tags plus the task's own.
+    pub tags: Vec<String>,
+    /// Expected bits ranges the run ended outside of.
+    pub violations: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct VersionInfo {
-    pub engine: &'static str,
-    pub build_token: Option<&'static str>,
-    pub git_ref: Option<&'static str>,
+    pub engine: String,
+    pub build_token: Option<String>,
+    pub git_ref: Option<String>,
     pub ts: String,
 }
 
@@ -328,9 +418,9 @@ impl VersionInfo {
     pub fn current() -> Self {
         let ts = chrono::Utc::now().to_rfc3339();
         Self {
-            engine: env!("CARGO_PKG_VERSION"),
-            build_token: option_env!("BUILD_TOKEN"),
-            git_ref: option_env!("GIT_REF"),
+            engine: env!("CARGO_PKG_VERSION").to_string(),
+            build_token: option_env!("BUILD_TOKEN").map(str::to_string),
+            git_ref: option_env!("GIT_REF").map(str::to_string),
             ts,
         }
     }
@@ -359,7 +449,8 @@ pub async fn run_handler(
     State(_state): State<AppState>,
     Json(req): Json<RunReq>,
 ) -> impl IntoResponse {
-    match run_with_integrations(&req.goal_id, req.inputs, &req.policy).await {
+    let ctx = RunCtx::new(None);
+    match run_with_integrations(&ctx, &req.goal_id, req.inputs, &req.policy).await {
         Ok((manifest, bits, pr_id, meta2_proposal)) => Json(RunResp {
             manifest,
             bits,
@@ -376,19 +467,256 @@ pub async fn run_handler(
     path = "/validate",
     request_body = ValidateReq,
     responses(
-        (status = 200, description = "Validation completed", body = ValidateResp)
+        (status = 200, description = "Validation completed", body = ValidateResp),
+        (status = 400, description = "Unknown suite or repeats outside 1..=100")
     )
 )]
 pub async fn validate_handler(
     State(_state): State<AppState>,
     Json(req): Json<ValidateReq>,
 ) -> impl IntoResponse {
-    match validate::run_suite(&req.suite).await {
+    match validate::run_suite(&req).await {
         Ok(resp) => Json(resp).into_response(),
         Err(e) => (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/validate/suites",
+    responses(
+        (status = 200, description = "Validation suites available to /validate", body = [SuiteInfo]),
+        (status = 500, description = "A suite file is invalid")
+    )
+)]
+pub async fn validate_suites_handler() -> impl IntoResponse {
+    match suites::load_all() {
+        Ok(all) => Json(all.iter().map(SuiteInfo::from).collect::<Vec<_>>()).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    pub suite: Option<String>,
+    /// Engine version, git ref or build token.
+    pub version: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/validate/history",
+    params(HistoryQuery),
+    responses((status = 200, description = "Stored validation runs, oldest first", body = [HistoryEntry]))
+)]
+pub async fn validate_history_handler(Query(q): Query<HistoryQuery>) -> impl IntoResponse {
+    let records = match history::list().await {
+        Ok(records) => records,
+        Err(e) => {
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    };
+    let entries: Vec<HistoryEntry> = match &q.version {
+        Some(version) => history::select(&records, version, q.suite.as_deref())
+            .into_iter()
+            .map(HistoryEntry::from)
+            .collect(),
+        None => records
+            .iter()
+            .filter(|r| q.suite.as_ref().is_none_or(|s| &r.suite == s))
+            .map(HistoryEntry::from)
+            .collect(),
+    };
+    Json(entries).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/validate/history/{id}",
+    responses(
+        (status = 200, description = "A stored validation run with its version, L2 params and full response"),
+        (status = 404, description = "Unknown validation run")
+    )
+)]
+pub async fn validate_record_handler(Path(id): Path<String>) -> impl IntoResponse {
+    match history::get(&id).await {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => (axum::http::StatusCode::NOT_FOUND, "unknown validation run").into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CompareQuery {
+    /// Validation id, or engine version / git ref / build token.
+    pub base: String,
+    pub head: String,
+    /// Restricts version selectors to one suite.
+    pub suite: Option<String>,
+    /// Significance level in (0, 1) (default 0.05).
+    pub alpha: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/validate/compare",
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "Per-task and calibration deltas with significant regressions flagged", body = Comparison),
+        (status = 400, description = "alpha is not between 0 and 1"),
+        (status = 404, description = "A selector matched no stored run")
+    )
+)]
+pub async fn validate_compare_handler(Query(q): Query<CompareQuery>) -> impl IntoResponse {
+    let alpha = q.alpha.unwrap_or(history::DEFAULT_ALPHA);
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            format!("alpha must be between 0 and 1 (exclusive), got {}", alpha),
+        )
+            .into_response();
+    }
+    let records = match history::list().await {
+        Ok(records) => records,
+        Err(e) => {
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    };
+    let base = history::select(&records, &q.base, q.suite.as_deref());
+    let head = history::select(&records, &q.head, q.suite.as_deref());
+    for (name, runs) in [(&q.base, &base), (&q.head, &head)] {
+        if runs.is_empty() {
+            return (
+                axum::http::StatusCode::NOT_FOUND,
+                format!("no validation run matches `{}`", name),
+            )
+                .into_response();
+        }
+    }
+    Json(history::compare(&base, &head, alpha)).into_response()
+}
+
+/// Candidate for a shadow evaluation: explicit params, or a proposal whose
+/// change is applied to the live params.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ShadowReq {
+    pub candidate: Option<L2Params>,
+    pub proposal: Option<Meta2Proposal>,
+    /// Fraction of live runs to sample; defaults to the proposal's
+    /// `shadow_pct`, else the kernel's `shadow_rollout_pct`.
+    pub pct: Option<f32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/shadow",
+    responses(
+        (status = 200, description = "Live vs candidate gate outcome rates of the running shadow", body = ShadowReport),
+        (status = 404, description = "No shadow evaluation is running")
+    )
+)]
+pub async fn shadow_handler() -> impl IntoResponse {
+    match shadow::report().await {
+        Some(report) => Json(report).into_response(),
+        None => (
+            axum::http::StatusCode::NOT_FOUND,
+            "no shadow evaluation is running",
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/shadow",
+    request_body = ShadowReq,
+    responses(
+        (status = 200, description = "Shadow started, replacing any running one", body = ShadowReport),
+        (status = 400, description = "Neither candidate nor proposal given, or the candidate is out of range"),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Not an admin")
+    )
+)]
+pub async fn shadow_start_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ShadowReq>,
+) -> impl IntoResponse {
+    if let Err(denied) = require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    let kernel = engine::kernel_snapshot().await;
+    let candidate = match (req.candidate, &req.proposal) {
+        (Some(candidate), _) => candidate,
+        (None, Some(proposal)) => proposal.change.apply(&kernel.l2_params),
+        (None, None) => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                "give a candidate or a proposal",
+            )
+                .into_response()
+        }
+    };
+    let pct = req
+        .pct
+        .or(req.proposal.as_ref().map(|p| p.shadow_pct))
+        .unwrap_or(kernel.l3_rules.shadow_rollout_pct);
+    match shadow::start(candidate, pct, req.proposal).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/shadow/promote",
+    responses(
+        (status = 200, description = "Candidate is now live; final shadow report", body = ShadowReport),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Not an admin"),
+        (status = 409, description = "No shadow running, no runs observed yet, or the candidate is out of range")
+    )
+)]
+pub async fn shadow_promote_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    match shadow::promote().await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (axum::http::StatusCode::CONFLICT, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/shadow",
+    responses(
+        (status = 200, description = "Shadow discarded; final report", body = ShadowReport),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Not an admin"),
+        (status = 404, description = "No shadow evaluation is running")
+    )
+)]
+pub async fn shadow_stop_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    match shadow::stop().await {
+        Some(report) => Json(report).into_response(),
+        None => (
+            axum::http::StatusCode::NOT_FOUND,
+            "no shadow evaluation is running",
+        )
+            .into_response(),
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/validate_golden",
@@ -459,44 +787,225 @@ pub async fn user_chat_handler(
         Some(u) if u.user_id == user_id => u,
         _ => return (axum::http::StatusCode::UNAUTHORIZED, "Invalid user").into_response(),
     };
-    let policy = req
-        .policy
-        .or(user.policy_overrides.clone())
-        .unwrap_or(Policy {
-            gamma_gate: 0.5,
-            time_ms: 30000,
-            max_risk: 0.3,
-            tiny_diff_loc: 120,
-        });
-
-    let run_id = format!("r-{}", uuid::Uuid::new_v4());
-    let tx = progress_tx().await;
-    let _ = tx.send(format!("{{\"run_id\":\"{}\",\"phase\":\"start\"}}", run_id));
+    let policy = resolve_policy(req.policy, &user).await;
+
+    let ctx = RunCtx::new(Some(user.user_id.clone()));
 
     // Use goal meta.omni
     let inputs = serde_json::json!({"message": req.message});
-    match run_with_integrations("meta.omni", inputs, &policy).await {
+    match run_with_integrations(&ctx, "meta.omni", inputs, &policy).await {
         Ok((manifest, bits, _pr, _m2)) => {
-            let reply = manifest
-                .evidence
-                .get("reply")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let _ = tx.send(format!("{{\"run_id\":\"{}\",\"phase\":\"done\"}}", run_id));
-            Json(ChatResp {
-                run_id,
-                user_id: user.user_id,
-                reply,
-                manifest,
-                bits,
-            })
-            .into_response()
+            Json(chat_resp(ctx.run_id, user.user_id, manifest, bits)).into_response()
         }
         Err(e) => (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
     }
 }
 
+fn chat_resp(run_id: String, user_id: String, manifest: Manifest, bits: Bits) -> ChatResp {
+    let reply = manifest
+        .evidence
+        .get("reply")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    ChatResp {
+        run_id,
+        user_id,
+        reply,
+        manifest,
+        bits,
+    }
+}
+
+/// Frames sent by chat WebSocket clients.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatClientFrame {
+    Message(ChatReq),
+    Approval { id: u64, approved: bool },
+}
+
+/// Frames sent to chat WebSocket clients. A turn is `start`, any number of
+/// `token`s, `provisional_bits` once the LM has answered, any
+/// `approval_request`s, then `final_bits` and `done` after the gates ran.
+/// A `reset` voids the turn's tokens so far; the reply streams again.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatServerFrame {
+    Start {
+        run_id: String,
+        thread: Option<String>,
+    },
+    Token {
+        run_id: String,
+        text: String,
+    },
+    Reset {
+        run_id: String,
+        reason: String,
+    },
+    ProvisionalBits {
+        run_id: String,
+        bits: Bits,
+    },
+    ApprovalRequest {
+        run_id: String,
+        id: u64,
+        capability: String,
+        action: String,
+    },
+    FinalBits {
+        run_id: String,
+        bits: Bits,
+    },
+    Done(ChatResp),
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatWsQuery {
+    // Browsers cannot set headers on a WebSocket handshake.
+    pub api_key: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/chat/ws",
+    responses(
+        (status = 101, description = "WebSocket chat with streamed reply tokens"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn user_chat_ws_handler(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Query(q): Query<ChatWsQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let user = match extract_api_key(&headers)
+        .or(q.api_key)
+        .and_then(|k| authenticate_user(&state, &k))
+    {
+        Some(u) if u.user_id == user_id => u,
+        _ => return (axum::http::StatusCode::UNAUTHORIZED, "Invalid user").into_response(),
+    };
+    ws.on_upgrade(move |socket| chat_socket(socket, user))
+}
+
+type ChatTurn = JoinHandle<anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)>>;
+
+async fn chat_socket(mut socket: WebSocket, user: UserContext) {
+    let (stream_tx, mut stream_rx) = mpsc::unbounded_channel();
+    let mut turn: Option<(String, ChatTurn)> = None;
+    let mut approvals: HashMap<u64, oneshot::Sender<bool>> = HashMap::new();
+    let mut next_approval = 0u64;
+
+    loop {
+        let frame = tokio::select! {
+            // Drain queued stream events before reporting the turn as finished.
+            biased;
+            Some(event) = stream_rx.recv() => {
+                let run_id = turn.as_ref().map(|(id, _)| id.clone()).unwrap_or_default();
+                match event {
+                    StreamEvent::Token(text) => ChatServerFrame::Token { run_id, text },
+                    StreamEvent::Reset(reason) => ChatServerFrame::Reset { run_id, reason },
+                    StreamEvent::ProvisionalBits(bits) => {
+                        ChatServerFrame::ProvisionalBits { run_id, bits }
+                    }
+                    StreamEvent::Approval(req) => {
+                        next_approval += 1;
+                        approvals.insert(next_approval, req.respond);
+                        ChatServerFrame::ApprovalRequest {
+                            run_id,
+                            id: next_approval,
+                            capability: req.capability,
+                            action: req.action,
+                        }
+                    }
+                }
+            }
+            msg = socket.recv() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                match serde_json::from_str::<ChatClientFrame>(&text) {
+                    Ok(ChatClientFrame::Message(_)) if turn.is_some() => ChatServerFrame::Error {
+                        message: "a turn is already in progress".to_string(),
+                    },
+                    Ok(ChatClientFrame::Message(req)) => {
+                        let mut ctx = RunCtx::new(Some(user.user_id.clone()));
+                        ctx.stream = Some(stream_tx.clone());
+                        let policy = resolve_policy(req.policy, &user).await;
+                        let run_id = ctx.run_id.clone();
+                        let inputs = serde_json::json!({"message": req.message});
+                        let handle = tokio::spawn(async move {
+                            run_with_integrations(&ctx, "meta.omni", inputs, &policy).await
+                        });
+                        turn = Some((run_id.clone(), handle));
+                        ChatServerFrame::Start {
+                            run_id,
+                            thread: req.thread,
+                        }
+                    }
+                    Ok(ChatClientFrame::Approval { id, approved }) => {
+                        match approvals.remove(&id) {
+                            Some(respond) => {
+                                let _ = respond.send(approved);
+                                continue;
+                            }
+                            None => ChatServerFrame::Error {
+                                message: format!("no pending approval {}", id),
+                            },
+                        }
+                    }
+                    Err(e) => ChatServerFrame::Error {
+                        message: format!("invalid frame: {}", e),
+                    },
+                }
+            }
+            joined = async {
+                match turn.as_mut() {
+                    Some((_, handle)) => handle.await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                let run_id = turn.take().map(|(id, _)| id).unwrap_or_default();
+                approvals.clear();
+                match joined {
+                    Ok(Ok((manifest, bits, _pr, _m2))) => {
+                        let final_bits = ChatServerFrame::FinalBits {
+                            run_id: run_id.clone(),
+                            bits: bits.clone(),
+                        };
+                        if send_frame(&mut socket, &final_bits).await.is_err() {
+                            break;
+                        }
+                        ChatServerFrame::Done(chat_resp(run_id, user.user_id.clone(), manifest, bits))
+                    }
+                    Ok(Err(e)) => ChatServerFrame::Error { message: e.to_string() },
+                    Err(e) => ChatServerFrame::Error { message: e.to_string() },
+                }
+            }
+        };
+        if send_frame(&mut socket, &frame).await.is_err() {
+            break;
+        }
+    }
+    if let Some((_, handle)) = turn {
+        handle.abort();
+    }
+}
+
+async fn send_frame(socket: &mut WebSocket, frame: &ChatServerFrame) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(frame).unwrap_or_default();
+    socket.send(Message::Text(text)).await
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ProgressQuery {
     pub run_id: Option<String>,
@@ -505,33 +1014,93 @@ pub struct ProgressQuery {
 #[utoipa::path(
     get,
     path = "/progress.sse",
-    responses((status = 200, description = "SSE progress stream"))
+    responses(
+        (status = 200, description = "SSE progress stream of the caller's runs, or of the anonymous run `run_id`"),
+        (status = 401, description = "Invalid x-api-key, or no key and no run_id")
+    )
 )]
 pub async fn progress_sse_handler(
+    State(state): State<AppState>,
     Query(q): Query<ProgressQuery>,
-) -> Sse<impl futures_core::Stream<Item = Result<Event, Infallible>>> {
-    let rx = progress_tx().await.subscribe();
-    let filter_id = q.run_id;
-    let stream = BroadcastStream::new(rx).filter_map(move |evt| {
-        let filter_id = filter_id.clone();
-        match evt {
-            Ok(payload) => {
-                if let Some(ref expected_id) = filter_id {
-                    let matches = serde_json::from_str::<Value>(&payload).ok().and_then(|v| {
-                        v.get("run_id")
-                            .and_then(|r| r.as_str())
-                            .map(|s| s.to_string())
-                    });
-                    if matches.as_deref() != Some(expected_id.as_str()) {
-                        return None;
-                    }
-                }
-                Some(Ok(Event::default().data(payload)))
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    // Without a key only an anonymous run followed by id can be streamed.
+    let tenant = match extract_api_key(&headers) {
+        Some(key) => match authenticate_user(&state, &key) {
+            Some(user) => Some(user.user_id),
+            None => {
+                return (axum::http::StatusCode::UNAUTHORIZED, "Invalid x-api-key").into_response()
             }
-            Err(_) => None,
-        }
+        },
+        None if q.run_id.is_some() => None,
+        None => return (axum::http::StatusCode::UNAUTHORIZED, "Invalid x-api-key").into_response(),
+    };
+    // Browsers resend the last seen id on reconnect; replay what they missed.
+    let last_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    let rx = progress::subscribe(tenant, q.run_id, last_id).await;
+    let stream = ReceiverStream::new(rx).filter_map(|event| {
+        Event::default()
+            .id(event.id.to_string())
+            .json_data(&event)
+            .ok()
+            .map(Ok::<_, Infallible>)
     });
     Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(15))
+                .text("heartbeat"),
+        )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/golden/{name}/record",
+    request_body = GoldenRecordReq,
+    responses(
+        (status = 200, description = "Recorded golden case (manifest, bits and LM/executor tape)"),
+        (status = 400, description = "Invalid name or the run failed"),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Not an admin")
+    )
+)]
+pub async fn golden_record_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(req): Json<GoldenRecordReq>,
+) -> impl IntoResponse {
+    if let Err(denied) = require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    let policy = match req.policy {
+        Some(policy) => policy,
+        None => policies::current().await.default_policy.clone(),
+    };
+    match engine::golden::record_golden(&name, &req.test, &req.goal_id, req.inputs, &policy).await {
+        Ok(case) => Json(case).into_response(),
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/golden/{name}/replay",
+    responses(
+        (status = 200, description = "Recorded cases re-run offline and diffed", body = ReplaySummary),
+        (status = 400, description = "Unknown or invalid golden file")
+    )
+)]
+pub async fn golden_replay_handler(Path(name): Path<String>) -> impl IntoResponse {
+    match engine::golden::replay_golden(&name).await {
+        Ok(summary) => Json(summary).into_response(),
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
 }
 
 #[utoipa::path(
@@ -565,18 +1134,65 @@ pub async fn golden_handler(Path(name): Path<String>) -> impl IntoResponse {
     }
 }
 
-async fn run_with_integrations(
+pub(crate) async fn run_with_integrations(
+    ctx: &RunCtx,
     goal_id: &str,
     inputs: serde_json::Value,
     policy: &Policy,
 ) -> anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)> {
-    // 1. Search flywheel for context
-    let _context = integrations::flywheel::search(goal_id).await?;
+    progress::emit(ctx, Phase::Start).await;
+    let out = async {
+        // 1. Search flywheel for context
+        let _context = integrations::flywheel::search(goal_id).await?;
+
+        // 2. Run engine with meta² layer
+        let out = engine::run(ctx, goal_id, inputs, policy).await?;
+        integrate(goal_id, out).await
+    }
+    .await;
+    finish(ctx, out).await
+}
+
+/// Continue a paused run from its checkpoint, then apply the same
+/// integrations as a fresh run.
+pub(crate) async fn resume_with_integrations(
+    ctx: &RunCtx,
+    input: ResumeInput,
+) -> anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)> {
+    progress::emit(ctx, Phase::Start).await;
+    let out = async {
+        let out = engine::resume(ctx, input).await?;
+        let goal_id = out.0.goal_id.clone();
+        integrate(&goal_id, out).await
+    }
+    .await;
+    finish(ctx, out).await
+}
+
+async fn finish(
+    ctx: &RunCtx,
+    out: anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)>,
+) -> anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)> {
+    match &out {
+        // A paused run already announced itself; it is not done yet.
+        Ok((manifest, _, _, _)) if checkpoint::is_paused(manifest) => {}
+        Ok((_, bits, _, _)) => progress::emit_bits(ctx, Phase::Done, bits, vec![]).await,
+        Err(_) => progress::emit(ctx, Phase::Failed).await,
+    }
+    out
+}
 
-    // 2. Run engine with meta² layer
-    let (manifest, ext_bits, meta2_proposal) = engine::run(goal_id, inputs, policy).await?;
+async fn integrate(
+    goal_id: &str,
+    (manifest, ext_bits, meta2_proposal): (Manifest, ExtendedBits, Option<Meta2Proposal>),
+) -> anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)> {
     let bits: Bits = ext_bits.into(); // Convert to legacy format
 
+    // Paused runs have produced nothing yet; integrations wait for the resume.
+    if checkpoint::is_paused(&manifest) {
+        return Ok((manifest, bits, None, None));
+    }
+
     // 3. Update flywheel metadata
     integrations::flywheel::update_metadata(goal_id, &manifest, bits.t).await?;
 
@@ -605,7 +1221,10 @@ async fn run_with_integrations(
     };
     let _ = integrations::kpi::track_kpi_impact(&goal_snapshot, bits.t).await;
 
-    // 6. Serialize meta² proposal if present
+    // 6. Hand off to a human lane when the run ended with its bits set
+    integrations::escalation::escalate(&manifest, &bits).await;
+
+    // 7. Serialize meta² proposal if present
     let meta2_json = meta2_proposal.map(|p| serde_json::to_string(&p).unwrap_or_default());
 
     Ok((manifest, bits, pr_id, meta2_json))
@@ -613,35 +1232,80 @@ async fn run_with_integrations(
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(version_handler, run_handler, validate_handler, validate_golden_handler, dashboard_handler, planning_handler, user_run_handler, user_status_handler, user_chat_handler, progress_sse_handler, golden_handler, research_index_handler, meta::meta_run_handler, meta::meta_state_handler, meta::meta_reset_handler, nstar::nstar_run_handler, nstar::nstar_hud_handler),
-    components(schemas(Bits, Policy, Manifest, RunReq, RunResp, VersionInfo, ValidateReq, ValidateResp, GoldenReq, GoldenResp, ValidationResult, UIState, AgentGoal, UserRunReq, UserRunResp, UserStatus, ChatReq, ChatResp, nstar::NStarRunReq, nstar::NStarRunResp, meta::MetaRunReq, meta::MetaRunResp, meta::MetaState)),
+    paths(version_handler, run_handler, validate_handler, validate_suites_handler, validate_history_handler, validate_record_handler, validate_compare_handler, shadow_handler, shadow_start_handler, shadow_promote_handler, shadow_stop_handler, validate_golden_handler, dashboard_handler, planning_handler, user_run_handler, user_status_handler, user_chat_handler, user_chat_ws_handler, progress_sse_handler, golden_handler, golden_record_handler, golden_replay_handler, research_index_handler, research_search_handler, research_stale_handler, research_reverify_handler, policies_handler, meta::meta_run_handler, meta::meta_state_handler, meta::meta_reset_handler, nstar::nstar_run_handler, nstar::nstar_hud_handler, jobs::submit_handler, jobs::status_handler, jobs::trace_handler, jobs::cancel_handler, jobs::resume_handler, jobs::clarify_handler, integrations::escalation::list_handler, integrations::escalation::claim_handler, integrations::escalation::resolve_handler),
+    components(schemas(Bits, Policy, Manifest, RunReq, RunResp, VersionInfo, ValidateReq, ValidateResp, Calibration, ReliabilityBin, SuiteInfo, HistoryEntry, Comparison, MetricDelta, TaskDelta, Summary, Interval, TaskAggregate, ResearchArtifact, SearchResp, SearchHit, Freshness, StaleArtifact, ReverifyReport, ShadowReq, ShadowReport, Rates, Observation, Outcome, L2Params, Meta2Proposal, Meta2Change, GoldenReq, GoldenRecordReq, GoldenResp, ReplaySummary, ReplayCase, ValidationResult, UIState, AgentGoal, UserRunReq, UserRunResp, UserStatus, ChatReq, ChatResp, nstar::NStarRunReq, nstar::NStarRunResp, meta::MetaRunReq, meta::MetaRunResp, meta::MetaState, ProgressEvent, Phase, GateDecision, jobs::JobStatus, jobs::JobRecord, jobs::RunSubmitResp, ResumeInput, PauseReason, RunStatus, jobs::ClarifyAnswer, ClarifyRequest, TriadSlot, integrations::escalation::Ticket, integrations::escalation::TicketStatus, integrations::escalation::ClaimReq, integrations::escalation::ResolveReq)),
     tags((name="one-engine", description="Multi-tenant metacognitive system"))
 )]
 pub struct ApiDoc;
 
+#[utoipa::path(
+    get,
+    path = "/policies",
+    responses((status = 200, description = "Active policy set with its version and content hash", body = Object))
+)]
+pub async fn policies_handler() -> impl IntoResponse {
+    Json((*policies::current().await).clone())
+}
+
 #[utoipa::path(
     get,
     path = "/research/index",
     responses((status = 200, description = "Research artifact index", body = [ResearchArtifact]))
 )]
 pub async fn research_index_handler() -> impl IntoResponse {
-    // Prefer on-disk index if present; else build from current workspace.
-    let disk = tokio::fs::read_to_string("research/index.jsonl").await;
-    let mut items: Vec<ResearchArtifact> = Vec::new();
-    if let Ok(s) = disk {
-        for line in s.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
-            if let Ok(a) = serde_json::from_str::<ResearchArtifact>(line) {
-                items.push(a);
-            }
-        }
-    } else {
-        // Fallback: build ephemeral index from '.' (no network)
-        if let Ok(v) = research::build_index(std::path::Path::new(".")) {
-            items = v;
+    Json(library::artifacts().await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/research/search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Ranked, paginated artifacts with snippets", body = SearchResp),
+        (status = 400, description = "Invalid path glob")
+    )
+)]
+pub async fn research_search_handler(Query(q): Query<SearchQuery>) -> impl IntoResponse {
+    let index = match library::search_index().await {
+        Ok(index) => index,
+        Err(e) => {
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
         }
+    };
+    match index.search(&q, chrono::Utc::now()) {
+        Ok(resp) => Json(resp).into_response(),
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/research/stale",
+    responses((status = 200, description = "Artifacts past their TTL, most overdue first", body = [StaleArtifact]))
+)]
+pub async fn research_stale_handler() -> impl IntoResponse {
+    Json(library::stale(chrono::Utc::now()).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/research/reverify",
+    responses(
+        (status = 200, description = "Stale artifacts re-hashed; unchanged ones refreshed", body = ReverifyReport),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Not an admin"),
+        (status = 500, description = "Index could not be read or written")
+    )
+)]
+pub async fn research_reverify_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    match library::reverify().await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
-    Json(items)
 }
@@ -0,0 +1,275 @@
+//! Assertions of golden cases, checked against the case's `result` and
+//! `bits`. An assertion is one of:
+//!
+//! - a string: a bits expression such as `U>=0.7 && E==1` (see [`expr`]);
+//! - an array: every element must hold;
+//! - an object, every key of which must hold:
+//!   - `bits`: a bits expression;
+//!   - `equals`: `{"/json/pointer": value}` into `result`;
+//!   - `matches`: `{"/json/pointer": "regex"}` into `result` (non-strings
+//!     are matched as their JSON text);
+//!   - `exists`: `["/json/pointer", ...]` into `result`;
+//!   - `all` / `any`: arrays of nested assertions; `not`: one assertion;
+//!   - `gate` + `expected`: `result.status` equals `expected` (and
+//!     `result.gate`, when present, names the gate);
+//!   - `replay`: informational; checked by replaying the recording.
+//!
+//! [`expr`]: super::expr
+
+use super::bits::Bits;
+use super::expr::{self, Expr};
+use regex::Regex;
+use serde_json::Value;
+
+/// Check `assertion`; on failure, one reason per failed part.
+pub fn check(assertion: &Value, result: &Value, bits: &Bits) -> Result<(), Vec<String>> {
+    let mut failures = Vec::new();
+    eval(assertion, result, bits, &mut failures);
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+fn eval(assertion: &Value, result: &Value, bits: &Bits, failures: &mut Vec<String>) {
+    match assertion {
+        Value::String(source) => check_bits(source, bits, failures),
+        Value::Array(items) => {
+            for item in items {
+                eval(item, result, bits, failures);
+            }
+        }
+        Value::Object(map) => {
+            for (key, arg) in map {
+                match key.as_str() {
+                    "bits" => match arg.as_str() {
+                        Some(source) => check_bits(source, bits, failures),
+                        None => failures.push("invalid assertion: `bits` must be a string".into()),
+                    },
+                    "equals" => {
+                        for_pointers(arg, "equals", failures, |ptr, want, failures| match result
+                            .pointer(ptr)
+                        {
+                            Some(got) if got == want => {}
+                            Some(got) => {
+                                failures.push(format!("{}: expected {}, got {}", ptr, want, got))
+                            }
+                            None => failures
+                                .push(format!("{}: expected {}, but it is missing", ptr, want)),
+                        })
+                    }
+                    "matches" => {
+                        for_pointers(arg, "matches", failures, |ptr, pattern, failures| {
+                            let Some(pattern) = pattern.as_str() else {
+                                failures.push(format!(
+                                    "invalid assertion: `matches` pattern for {} must be a string",
+                                    ptr
+                                ));
+                                return;
+                            };
+                            let re = match Regex::new(pattern) {
+                                Ok(re) => re,
+                                Err(e) => {
+                                    failures.push(format!(
+                                        "invalid assertion: regex /{}/: {}",
+                                        pattern, e
+                                    ));
+                                    return;
+                                }
+                            };
+                            match result.pointer(ptr) {
+                                Some(Value::String(s)) if re.is_match(s) => {}
+                                Some(got @ Value::String(_)) => failures
+                                    .push(format!("{}: {} does not match /{}/", ptr, got, pattern)),
+                                Some(got) if re.is_match(&got.to_string()) => {}
+                                Some(got) => failures
+                                    .push(format!("{}: {} does not match /{}/", ptr, got, pattern)),
+                                None => failures.push(format!(
+                                    "{}: missing, expected to match /{}/",
+                                    ptr, pattern
+                                )),
+                            }
+                        })
+                    }
+                    "exists" => match arg.as_array() {
+                        Some(ptrs) => {
+                            for ptr in ptrs {
+                                match ptr.as_str() {
+                                    Some(p) if result.pointer(p).is_some() => {}
+                                    Some(p) => failures.push(format!(
+                                        "{}: expected to exist, but it is missing",
+                                        p
+                                    )),
+                                    None => failures.push(
+                                        "invalid assertion: `exists` takes pointer strings".into(),
+                                    ),
+                                }
+                            }
+                        }
+                        None => {
+                            failures.push("invalid assertion: `exists` must be an array".into())
+                        }
+                    },
+                    "all" => eval(arg, result, bits, failures),
+                    "any" => {
+                        let Some(items) = arg.as_array() else {
+                            failures.push("invalid assertion: `any` must be an array".into());
+                            continue;
+                        };
+                        let mut reasons = Vec::new();
+                        let held = items.iter().any(|item| {
+                            let mut f = Vec::new();
+                            eval(item, result, bits, &mut f);
+                            reasons.extend(f.iter().cloned());
+                            f.is_empty()
+                        });
+                        if !held {
+                            failures.push(format!("none of `any` held ({})", reasons.join("; ")));
+                        }
+                    }
+                    "not" => {
+                        let mut f = Vec::new();
+                        eval(arg, result, bits, &mut f);
+                        if f.is_empty() {
+                            failures.push(format!("`not` held: {}", arg));
+                        }
+                    }
+                    "gate" => check_gate(arg, map.get("expected"), result, failures),
+                    "expected" if map.contains_key("gate") => {}
+                    "replay" => {}
+                    other => failures.push(format!("invalid assertion: unknown key `{}`", other)),
+                }
+            }
+        }
+        other => failures.push(format!("invalid assertion: {}", other)),
+    }
+}
+
+/// Run `check` for every `pointer: value` pair of an `equals`/`matches` map.
+fn for_pointers(
+    arg: &Value,
+    name: &str,
+    failures: &mut Vec<String>,
+    mut check: impl FnMut(&str, &Value, &mut Vec<String>),
+) {
+    match arg.as_object() {
+        Some(map) => {
+            for (ptr, want) in map {
+                check(ptr, want, failures);
+            }
+        }
+        None => failures.push(format!(
+            "invalid assertion: `{}` must map JSON pointers to values",
+            name
+        )),
+    }
+}
+
+fn check_gate(gate: &Value, expected: Option<&Value>, result: &Value, failures: &mut Vec<String>) {
+    let Some(expected) = expected else {
+        failures.push(format!(
+            "invalid assertion: gate {} has no `expected`",
+            gate
+        ));
+        return;
+    };
+    if let Some(named) = result.get("gate") {
+        if named != gate {
+            failures.push(format!("gate: expected {}, got {}", gate, named));
+        }
+    }
+    match result.get("status") {
+        Some(status) if status == expected => {}
+        Some(status) => failures.push(format!(
+            "gate {}: expected status {}, got {}",
+            gate, expected, status
+        )),
+        None => failures.push(format!(
+            "gate {}: expected status {}, but result has no status",
+            gate, expected
+        )),
+    }
+}
+
+fn check_bits(source: &str, bits: &Bits, failures: &mut Vec<String>) {
+    let expr = match Expr::parse(source) {
+        Ok(expr) => expr,
+        Err(e) => {
+            failures.push(format!("invalid assertion: `{}`: {}", source, e));
+            return;
+        }
+    };
+    let bit = |name: &str| {
+        Some(match name {
+            "A" => bits.a,
+            "U" => bits.u,
+            "P" => bits.p,
+            "E" => bits.e,
+            "Δ" | "D" => bits.d,
+            "I" => bits.i,
+            "R" => bits.r,
+            "T" => bits.t,
+            "M" => bits.m,
+            _ => return None,
+        })
+    };
+    // Widen via the shortest decimal form so `T>=0.9` holds for an f32 0.9.
+    let widen = |v: f32| v.to_string().parse::<f64>().unwrap_or(v as f64);
+    match expr.holds(&|name| bit(name).map(|v| expr::Value::Num(widen(v)))) {
+        Ok(true) => {}
+        Ok(false) => {
+            let values: Vec<String> = expr
+                .idents()
+                .iter()
+                .filter_map(|name| bit(name).map(|v| format!("{}={:.2}", name, v)))
+                .collect();
+            failures.push(format!(
+                "bits `{}` is false ({})",
+                source,
+                values.join(", ")
+            ));
+        }
+        Err(e) => failures.push(format!("bits `{}`: {}", source, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reasons_name_the_failed_assertion() {
+        let bits = Bits {
+            a: 1.0,
+            u: 0.1,
+            p: 1.0,
+            e: 0.0,
+            d: 0.0,
+            i: 0.0,
+            r: 0.0,
+            t: 0.9,
+            m: 0.0,
+        };
+        let result = json!({"status": "open", "evidence": "risk_below_threshold", "n": 3});
+        let passing = json!([
+            "U<0.5 && T>=0.9",
+            {"gate": "ask_act", "expected": "open"},
+            {"equals": {"/n": 3}, "matches": {"/evidence": "^risk_"}, "exists": ["/status"]},
+            {"any": ["E==1", {"not": "A==0"}]}
+        ]);
+        assert_eq!(check(&passing, &result, &bits), Ok(()));
+
+        let failing =
+            json!({"bits": "U>=0.7", "equals": {"/status": "deny"}, "exists": ["/policy"]});
+        assert_eq!(
+            check(&failing, &result, &bits).unwrap_err(),
+            [
+                "bits `U>=0.7` is false (U=0.10)",
+                "/status: expected \"deny\", got \"open\"",
+                "/policy: expected to exist, but it is missing",
+            ]
+        );
+    }
+}
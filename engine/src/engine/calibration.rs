@@ -0,0 +1,143 @@
+//! Calibration statistics over a validation suite, treating T as the
+//! predicted probability of success and U as a failure detector.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const DEFAULT_BINS: usize = 10;
+pub const MAX_BINS: usize = 100;
+
+/// One run: predicted success probability (T), uncertainty (U) and outcome.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub t: f32,
+    pub u: f32,
+    pub success: bool,
+}
+
+/// One bar of a reliability diagram: runs whose T fell in `[lower, upper)`
+/// (the last bin includes 1.0).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ReliabilityBin {
+    pub lower: f32,
+    pub upper: f32,
+    pub count: usize,
+    /// Mean T of the bin; `None` when empty.
+    pub confidence: Option<f32>,
+    /// Observed success rate of the bin; `None` when empty.
+    pub accuracy: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Calibration {
+    pub samples: usize,
+    /// Mean squared error of T against success (0 is perfect).
+    pub brier: f32,
+    /// Expected calibration error: count-weighted |accuracy − confidence|.
+    pub ece: f32,
+    /// Probability a failed run has higher U than a successful one; `None`
+    /// unless the suite had both.
+    pub auroc_u_failure: Option<f32>,
+    pub bins: Vec<ReliabilityBin>,
+}
+
+pub fn compute(samples: &[Sample], bins: usize) -> Calibration {
+    let bins = bins.clamp(1, MAX_BINS);
+    let n = samples.len();
+    let outcome = |s: &Sample| if s.success { 1.0 } else { 0.0 };
+
+    let brier = if n == 0 {
+        0.0
+    } else {
+        samples
+            .iter()
+            .map(|s| (s.t.clamp(0.0, 1.0) - outcome(s)).powi(2))
+            .sum::<f32>()
+            / n as f32
+    };
+
+    let mut grouped: Vec<Vec<&Sample>> = vec![Vec::new(); bins];
+    for s in samples {
+        let i = ((s.t.clamp(0.0, 1.0) * bins as f32) as usize).min(bins - 1);
+        grouped[i].push(s);
+    }
+    let mut ece = 0.0;
+    let reliability = grouped
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let count = group.len();
+            let (confidence, accuracy) = if count == 0 {
+                (None, None)
+            } else {
+                let conf = group.iter().map(|s| s.t.clamp(0.0, 1.0)).sum::<f32>() / count as f32;
+                let acc = group.iter().map(|s| outcome(s)).sum::<f32>() / count as f32;
+                ece += count as f32 / n as f32 * (acc - conf).abs();
+                (Some(conf), Some(acc))
+            };
+            ReliabilityBin {
+                lower: i as f32 / bins as f32,
+                upper: (i + 1) as f32 / bins as f32,
+                count,
+                confidence,
+                accuracy,
+            }
+        })
+        .collect();
+
+    Calibration {
+        samples: n,
+        brier,
+        ece,
+        auroc_u_failure: auroc(samples),
+        bins: reliability,
+    }
+}
+
+/// Mann–Whitney AUROC of U as a score for failure; ties count half.
+fn auroc(samples: &[Sample]) -> Option<f32> {
+    let failed: Vec<f32> = samples.iter().filter(|s| !s.success).map(|s| s.u).collect();
+    let passed: Vec<f32> = samples.iter().filter(|s| s.success).map(|s| s.u).collect();
+    if failed.is_empty() || passed.is_empty() {
+        return None;
+    }
+    let mut wins = 0.0;
+    for f in &failed {
+        for p in &passed {
+            wins += if f > p {
+                1.0
+            } else if f == p {
+                0.5
+            } else {
+                0.0
+            };
+        }
+    }
+    Some(wins / (failed.len() * passed.len()) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_and_inverted_predictions() {
+        let sample = |t, u, success| Sample { t, u, success };
+        let perfect = [sample(1.0, 0.1, true), sample(0.0, 0.9, false)];
+        let cal = compute(&perfect, 10);
+        assert_eq!(cal.brier, 0.0);
+        assert_eq!(cal.ece, 0.0);
+        assert_eq!(cal.auroc_u_failure, Some(1.0));
+        assert_eq!(cal.bins.len(), 10);
+        assert_eq!(cal.bins[9].count, 1);
+
+        let inverted = [sample(0.0, 0.9, true), sample(1.0, 0.1, false)];
+        let cal = compute(&inverted, 2);
+        assert_eq!(cal.brier, 1.0);
+        assert_eq!(cal.ece, 1.0);
+        assert_eq!(cal.auroc_u_failure, Some(0.0));
+
+        assert_eq!(compute(&perfect[..1], 5).auroc_u_failure, None);
+    }
+}
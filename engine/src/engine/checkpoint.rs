@@ -0,0 +1,324 @@
+use super::clarify::ClarifyRequest;
+use super::context::ContextVerdict;
+use super::executor::{Action, ExecResult};
+use super::kernel::{ExtendedBits, Meta2Proposal};
+use super::progress::Phase;
+use super::types::{Manifest, Policy, RunCtx};
+use anyhow::Context;
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+const DEFAULT_RETENTION_SECS: i64 = 7 * 24 * 3600;
+const PRUNE_EVERY_SECS: u64 = 3600;
+
+/// Boundaries from `policies/BOUNDARIES.yaml`, in execution order.
+pub const BOUNDARIES: [Phase; 5] = [
+    Phase::FetchInputs,
+    Phase::PlanActions,
+    Phase::VerifyDryRun,
+    Phase::ActApply,
+    Phase::VerifyPost,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Why a run stopped at a boundary and what it needs to continue.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PauseReason {
+    Clarification {
+        detail: String,
+        #[serde(default)]
+        request: Option<ClarifyRequest>,
+    },
+    Approval {
+        capability: String,
+        action: String,
+    },
+}
+
+impl PauseReason {
+    /// Manifest deliverable marking a paused run.
+    pub fn deliverable(&self) -> &'static str {
+        match self {
+            PauseReason::Clarification { .. } => "clarification_required",
+            PauseReason::Approval { .. } => "approval_required",
+        }
+    }
+}
+
+/// True when `manifest` describes a run waiting at a boundary.
+pub fn is_paused(manifest: &Manifest) -> bool {
+    manifest
+        .deliverables
+        .iter()
+        .any(|d| d == "clarification_required" || d == "approval_required")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Plan {
+    Chat {
+        message: String,
+    },
+    Cli {
+        action: Action,
+        expected_success: bool,
+    },
+}
+
+/// A live gate decision and the bits it was taken on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateSeen {
+    pub gate: String,
+    pub open: bool,
+    pub bits: ExtendedBits,
+}
+
+/// Everything needed to continue a run from its last completed boundary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    pub run_id: String,
+    pub tenant: Option<String>,
+    pub goal_id: String,
+    pub inputs: Value,
+    pub policy: Policy,
+    pub status: RunStatus,
+    pub bits: ExtendedBits,
+    pub completed: Vec<Phase>,
+    pub pause: Option<PauseReason>,
+    /// Capabilities the client has consented to for this run.
+    #[serde(default)]
+    pub approvals: Vec<String>,
+    pub plan: Option<Plan>,
+    pub exec: Option<ExecResult>,
+    pub lm_result: Option<Value>,
+    /// Per-item verdicts for `inputs.context`, from `fetch_inputs`.
+    #[serde(default)]
+    pub context: Vec<ContextVerdict>,
+    #[serde(default)]
+    pub deliverables: Vec<String>,
+    /// The `ask_act` and `evidence` decisions as taken, for shadowing.
+    #[serde(default)]
+    pub gates_seen: Vec<GateSeen>,
+    pub meta2_proposal: Option<Meta2Proposal>,
+    pub error: Option<String>,
+    pub updated_at: String,
+}
+
+impl RunState {
+    pub fn new(ctx: &RunCtx, goal_id: &str, inputs: Value, policy: &Policy) -> Self {
+        Self {
+            run_id: ctx.run_id.clone(),
+            tenant: ctx.tenant.clone(),
+            goal_id: goal_id.to_string(),
+            inputs,
+            policy: policy.clone(),
+            status: RunStatus::Running,
+            bits: ExtendedBits::init(),
+            completed: Vec::new(),
+            pause: None,
+            approvals: Vec::new(),
+            plan: None,
+            exec: None,
+            lm_result: None,
+            context: Vec::new(),
+            deliverables: Vec::new(),
+            gates_seen: Vec::new(),
+            meta2_proposal: None,
+            error: None,
+            updated_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Record a gate decision, replacing an earlier one of the same gate
+    /// (a resumed run re-taking it).
+    pub fn saw(&mut self, gate: &str, open: bool, bits: &ExtendedBits) {
+        self.gates_seen.retain(|g| g.gate != gate);
+        self.gates_seen.push(GateSeen {
+            gate: gate.to_string(),
+            open,
+            bits: bits.clone(),
+        });
+    }
+
+    pub fn gate_seen(&self, gate: &str) -> Option<&GateSeen> {
+        self.gates_seen.iter().find(|g| g.gate == gate)
+    }
+
+    pub fn next_boundary(&self) -> Option<Phase> {
+        BOUNDARIES
+            .iter()
+            .copied()
+            .find(|p| !self.completed.contains(p))
+    }
+}
+
+pub fn store_dir() -> PathBuf {
+    PathBuf::from(std::env::var("RUN_STORE").unwrap_or_else(|_| "trace/runs".to_string()))
+}
+
+fn state_path(run_id: &str) -> anyhow::Result<PathBuf> {
+    run_path(run_id, "json")
+}
+
+/// `<store>/<run_id>.<ext>`, for the checkpoint and files kept beside it.
+pub fn run_path(run_id: &str, ext: &str) -> anyhow::Result<PathBuf> {
+    // Run ids are generated server-side; refuse anything that could escape the store.
+    if run_id.is_empty()
+        || !run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("invalid run id: {}", run_id);
+    }
+    Ok(store_dir().join(format!("{}.{}", run_id, ext)))
+}
+
+/// Persist a checkpoint atomically (write to a temp file, then rename).
+pub async fn save(state: &mut RunState) -> anyhow::Result<()> {
+    state.updated_at = Utc::now().to_rfc3339();
+    let path = state_path(&state.run_id)?;
+    tokio::fs::create_dir_all(store_dir()).await?;
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(state)?).await?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .with_context(|| format!("failed to write checkpoint {}", path.display()))?;
+    Ok(())
+}
+
+/// Delete the checkpoints and traces in `dir` of runs that finished more
+/// than `retention` before `now`, and traces left without a checkpoint
+/// that long. Paused and interrupted runs are kept so they can still be
+/// resumed. Returns how many runs were removed.
+pub async fn prune(
+    dir: &Path,
+    now: chrono::DateTime<Utc>,
+    retention: chrono::Duration,
+) -> anyhow::Result<usize> {
+    #[derive(Deserialize)]
+    struct Header {
+        status: RunStatus,
+        updated_at: String,
+    }
+    let cutoff = now - retention;
+    let mut removed = 0;
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => {
+                let Ok(text) = tokio::fs::read_to_string(&path).await else {
+                    continue;
+                };
+                let Ok(header) = serde_json::from_str::<Header>(&text) else {
+                    continue;
+                };
+                let finished = matches!(
+                    header.status,
+                    RunStatus::Completed | RunStatus::Failed | RunStatus::Cancelled
+                );
+                let old = chrono::DateTime::parse_from_rfc3339(&header.updated_at)
+                    .is_ok_and(|at| at < cutoff);
+                if finished && old {
+                    tokio::fs::remove_file(&path).await?;
+                    let _ = tokio::fs::remove_file(path.with_extension("jsonl")).await;
+                    removed += 1;
+                }
+            }
+            Some("jsonl") if !path.with_extension("json").exists() => {
+                let modified = entry.metadata().await?.modified()?;
+                if chrono::DateTime::<Utc>::from(modified) < cutoff {
+                    tokio::fs::remove_file(&path).await?;
+                    removed += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(removed)
+}
+
+/// Prune the run store hourly, keeping finished runs for
+/// `RUN_RETENTION_SECS` (default 7 days).
+pub fn spawn_pruner() {
+    let secs = std::env::var("RUN_RETENTION_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_RETENTION_SECS);
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(PRUNE_EVERY_SECS));
+        loop {
+            tick.tick().await;
+            let dir = store_dir();
+            if !dir.exists() {
+                continue;
+            }
+            match prune(&dir, Utc::now(), chrono::Duration::seconds(secs)).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Pruned {} finished runs from {}", n, dir.display()),
+                Err(e) => tracing::warn!("Run store prune failed: {}", e),
+            }
+        }
+    });
+}
+
+pub async fn load(run_id: &str) -> anyhow::Result<RunState> {
+    let path = state_path(run_id)?;
+    let s = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("no checkpoint for run {}", run_id))?;
+    Ok(serde_json::from_str(&s)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn prune_removes_only_old_finished_runs() {
+        let dir = std::env::temp_dir().join(format!("runs-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let now = Utc::now();
+        let old = (now - chrono::Duration::days(10)).to_rfc3339();
+        let write = |id: &str, status: &str, updated_at: &str| {
+            let header = serde_json::json!({"status": status, "updated_at": updated_at});
+            std::fs::write(dir.join(format!("{}.json", id)), header.to_string()).unwrap();
+            std::fs::write(dir.join(format!("{}.jsonl", id)), "{}\n").unwrap();
+        };
+        write("old-done", "completed", &old);
+        write("old-paused", "paused", &old);
+        write("recent-done", "completed", &now.to_rfc3339());
+
+        let removed = prune(&dir, now, chrono::Duration::days(7)).await.unwrap();
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        left.sort();
+        assert_eq!(removed, 1);
+        assert_eq!(
+            left,
+            [
+                "old-paused.json",
+                "old-paused.jsonl",
+                "recent-done.json",
+                "recent-done.jsonl"
+            ]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use super::kernel::ExtendedBits;
+use super::{context, library};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// The three parts of a well-posed task in `PROMPT_TEMPLATES/triad.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriadSlot {
+    Goal,
+    Constraints,
+    Evidence,
+}
+
+impl TriadSlot {
+    const ALL: [TriadSlot; 3] = [TriadSlot::Goal, TriadSlot::Constraints, TriadSlot::Evidence];
+
+    /// Input field the slot is read from and answers are merged into.
+    pub fn key(self) -> &'static str {
+        match self {
+            TriadSlot::Goal => "goal",
+            TriadSlot::Constraints => "constraints",
+            TriadSlot::Evidence => "evidence",
+        }
+    }
+
+    fn heading(self) -> &'static str {
+        match self {
+            TriadSlot::Goal => "Goal:",
+            TriadSlot::Constraints => "Constraints:",
+            TriadSlot::Evidence => "Evidence:",
+        }
+    }
+
+    fn question(self, bits: &ExtendedBits) -> &'static str {
+        match self {
+            TriadSlot::Goal => "What single outcome should this run produce?",
+            TriadSlot::Constraints => "Which constraints must the action respect?",
+            TriadSlot::Evidence if bits.d > 0.0 => {
+                "The supplied context has expired; what current evidence should the run rely on?"
+            }
+            TriadSlot::Evidence => "What evidence (link or log reference) supports acting?",
+        }
+    }
+}
+
+/// A clarification request rendered from the ask-act template.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ClarifyRequest {
+    /// Template the request was rendered from.
+    pub template: String,
+    /// Triad parts absent from the run's inputs.
+    pub missing: Vec<TriadSlot>,
+    /// The slot the single question fills.
+    pub ask: TriadSlot,
+    pub question: String,
+    /// Why the gate blocked, e.g. `A=1, P=1, Δ=1`.
+    pub gate: String,
+    /// The template filled in with what the run already knows.
+    pub rendered: String,
+}
+
+/// Values already supplied for `slot`; `message` stands in for a goal.
+fn slot_values(inputs: &Value, slot: TriadSlot) -> Vec<String> {
+    let mut field = inputs.get(slot.key());
+    if slot == TriadSlot::Goal && field.is_none() {
+        field = inputs.get("message");
+    }
+    match field {
+        Some(Value::String(s)) if !s.trim().is_empty() => vec![s.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .filter(|s| !s.trim().is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Build the clarification for a blocked ask-act gate. The question targets
+/// the part the failing bit points at (Δ → evidence, P → constraints,
+/// A → goal) and otherwise the first part missing from `inputs`.
+/// `template` is the ask-act policy's `clarify_template`.
+pub fn request(inputs: &Value, bits: &ExtendedBits, gate: &str, template: &str) -> ClarifyRequest {
+    let missing: Vec<TriadSlot> = TriadSlot::ALL
+        .into_iter()
+        .filter(|slot| slot_values(inputs, *slot).is_empty())
+        .collect();
+    let ask = if bits.d > 0.0 {
+        TriadSlot::Evidence
+    } else if bits.p < 1.0 {
+        TriadSlot::Constraints
+    } else if bits.a < 1.0 {
+        TriadSlot::Goal
+    } else {
+        missing.first().copied().unwrap_or(TriadSlot::Evidence)
+    };
+    let question = ask.question(bits).to_string();
+    let source = std::fs::read_to_string(template).unwrap_or_default();
+    let rendered = render(&source, inputs, &question);
+    ClarifyRequest {
+        template: template.to_string(),
+        missing,
+        ask,
+        question,
+        gate: gate.to_string(),
+        rendered,
+    }
+}
+
+/// Replace each section's `- <placeholder>` bullets with the known values
+/// (or `- (missing)`), and append the question.
+fn render(template: &str, inputs: &Value, question: &str) -> String {
+    let mut out = Vec::new();
+    let mut section: Option<TriadSlot> = None;
+    for line in template.lines() {
+        let trimmed = line.trim();
+        if let Some(slot) = TriadSlot::ALL.into_iter().find(|s| trimmed == s.heading()) {
+            section = Some(slot);
+            out.push(line.to_string());
+            let values = slot_values(inputs, slot);
+            if values.is_empty() {
+                out.push("- (missing)".to_string());
+            }
+            out.extend(values.into_iter().map(|v| format!("- {}", v)));
+            continue;
+        }
+        if section.is_some() && trimmed.starts_with("- <") {
+            continue;
+        }
+        if trimmed.is_empty() {
+            section = None;
+        }
+        out.push(line.to_string());
+    }
+    while out.last().is_some_and(|l| l.trim().is_empty()) {
+        out.pop();
+    }
+    out.push(String::new());
+    out.push(format!("Question: {}", question));
+    out.join("\n")
+}
+
+/// Inputs patch that merges `answer` into the slot it was asked for. An
+/// evidence answer supersedes expired, drifted or rejected `context` items
+/// and `research` citations, which are dropped so the next freshness check
+/// passes.
+pub async fn answer_patch(inputs: &Value, ask: TriadSlot, answer: &str) -> Value {
+    let mut patch = serde_json::Map::new();
+    match ask {
+        TriadSlot::Goal => {
+            patch.insert(ask.key().to_string(), Value::String(answer.to_string()));
+        }
+        TriadSlot::Constraints | TriadSlot::Evidence => {
+            let mut values: Vec<Value> = match inputs.get(ask.key()) {
+                Some(Value::Array(items)) => items.clone(),
+                Some(Value::String(s)) => vec![Value::String(s.clone())],
+                _ => Vec::new(),
+            };
+            values.push(Value::String(answer.to_string()));
+            patch.insert(ask.key().to_string(), Value::Array(values));
+        }
+    }
+    if ask == TriadSlot::Evidence {
+        if let Some(items) = inputs.get("context").and_then(|v| v.as_array()) {
+            let fresh: Vec<Value> = items
+                .iter()
+                .enumerate()
+                .filter(|(n, i)| context::assess_item(*n, i).verdict.usable())
+                .map(|(_, i)| i.clone())
+                .collect();
+            patch.insert("context".to_string(), Value::Array(fresh));
+        }
+        if let Some(research) = inputs.get("research").filter(|v| !v.is_null()) {
+            let cited = match research {
+                Value::Array(items) => items.clone(),
+                other => vec![other.clone()],
+            };
+            let verdicts = library::cite(Some(research)).await;
+            let fresh: Vec<Value> = cited
+                .into_iter()
+                .zip(verdicts)
+                .filter(|(_, v)| v.verdict.usable())
+                .map(|(c, _)| c)
+                .collect();
+            patch.insert("research".to_string(), Value::Array(fresh));
+        }
+    }
+    Value::Object(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn stale_context_asks_for_evidence_and_answer_clears_it() {
+        let inputs = json!({
+            "message": "deploy",
+            "context": [{"ts": "2020-01-01T00:00:00Z", "ttl": 10}]
+        });
+        let mut bits = ExtendedBits::init();
+        bits.d = 1.0;
+        let triad =
+            "Goal:\n- <one-line>\n\nConstraints:\n- <list 1-3>\n\nEvidence:\n- <links/log refs>\n";
+        let rendered = render(triad, &inputs, "q?");
+        assert!(rendered.contains("Goal:\n- deploy\n"));
+        assert!(rendered.contains("Constraints:\n- (missing)\n"));
+
+        let req = request(&inputs, &bits, "Δ=1", "PROMPT_TEMPLATES/triad.md");
+        assert_eq!(req.ask, TriadSlot::Evidence);
+        assert_eq!(
+            req.missing,
+            vec![TriadSlot::Constraints, TriadSlot::Evidence]
+        );
+
+        let patch = answer_patch(&inputs, req.ask, "log://build/42").await;
+        assert_eq!(
+            patch,
+            json!({"evidence": ["log://build/42"], "context": []})
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use super::kernel::ExtendedBits;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+/// An `inputs.context[]` entry, per `schemas/CONTEXT.schema.json`.
+/// `content` is optional inline content the `hash` can be checked against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextItem {
+    pub id: String,
+    pub src: String,
+    pub ts: DateTime<Utc>,
+    pub ttl: u64,
+    pub hash: String,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// Within its TTL and the hash matches its content.
+    Fresh,
+    /// Within its TTL, but the content could not be checked.
+    Unverified,
+    /// Older than its TTL.
+    Expired,
+    /// The content no longer matches the recorded hash.
+    Drifted,
+    /// Not a valid context item; it was not used.
+    Rejected,
+}
+
+impl Verdict {
+    /// Whether the item may still back a decision.
+    pub fn usable(self) -> bool {
+        matches!(self, Verdict::Fresh | Verdict::Unverified)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextVerdict {
+    pub index: usize,
+    pub id: Option<String>,
+    pub src: Option<String>,
+    pub verdict: Verdict,
+    pub age_s: Option<i64>,
+    pub reason: Option<String>,
+}
+
+/// Assess every item of `inputs.context`. A missing field yields no verdicts.
+pub fn assess(context: Option<&Value>) -> Vec<ContextVerdict> {
+    match context {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| assess_item(i, item))
+            .collect(),
+        Some(_) => vec![rejected(0, None, "context must be an array of items")],
+    }
+}
+
+pub fn assess_item(index: usize, raw: &Value) -> ContextVerdict {
+    let field = |name: &str| raw.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    if field("src").is_none_or(|s| s.trim().is_empty()) {
+        return rejected(index, field("id"), "missing provenance (src)");
+    }
+    let item: ContextItem = match serde_json::from_value(raw.clone()) {
+        Ok(item) => item,
+        Err(e) => return rejected(index, field("id"), &format!("invalid context item: {}", e)),
+    };
+
+    let age = (Utc::now() - item.ts).num_seconds();
+    let (verdict, reason) = if age > item.ttl as i64 {
+        (
+            Verdict::Expired,
+            Some(format!("age {}s exceeds ttl {}s", age, item.ttl)),
+        )
+    } else {
+        match check_hash(&item) {
+            Ok(true) => (Verdict::Fresh, None),
+            Ok(false) => (
+                Verdict::Drifted,
+                Some("content hash no longer matches".to_string()),
+            ),
+            Err(why) => (Verdict::Unverified, Some(why)),
+        }
+    };
+    ContextVerdict {
+        index,
+        id: Some(item.id),
+        src: Some(item.src),
+        verdict,
+        age_s: Some(age),
+        reason,
+    }
+}
+
+/// Δ when any item is expired or has drifted, I when any was rejected.
+pub fn mark_bits(verdicts: &[ContextVerdict], bits: &mut ExtendedBits) {
+    for item in verdicts {
+        match item.verdict {
+            Verdict::Expired | Verdict::Drifted => bits.d = 1.0,
+            Verdict::Rejected => bits.i = 1.0,
+            Verdict::Fresh | Verdict::Unverified => {}
+        }
+    }
+}
+
+fn rejected(index: usize, id: Option<String>, reason: &str) -> ContextVerdict {
+    ContextVerdict {
+        index,
+        id,
+        src: None,
+        verdict: Verdict::Rejected,
+        age_s: None,
+        reason: Some(reason.to_string()),
+    }
+}
+
+/// Directory `src` file references are read from (`CONTEXT_DIR`, default
+/// `context`).
+pub fn context_dir() -> PathBuf {
+    PathBuf::from(std::env::var("CONTEXT_DIR").unwrap_or_else(|_| "context".to_string()))
+}
+
+/// Re-hash the referenced content: inline `content` if present, else `src`
+/// when it names a file inside [`context_dir`]. Remote sources are not
+/// fetched.
+fn check_hash(item: &ContextItem) -> Result<bool, String> {
+    check_hash_in(item, &context_dir())
+}
+
+fn check_hash_in(item: &ContextItem, root: &Path) -> Result<bool, String> {
+    let expected = item
+        .hash
+        .strip_prefix("sha256:")
+        .unwrap_or(&item.hash)
+        .to_ascii_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("hash is not a sha256 digest".to_string());
+    }
+    let content = match &item.content {
+        Some(content) => content.as_bytes().to_vec(),
+        None => {
+            let path = item.src.strip_prefix("file://").unwrap_or(&item.src);
+            let path = Path::new(path);
+            if item.src.contains("://") && !item.src.starts_with("file://") {
+                return Err("remote source not fetched".to_string());
+            }
+            if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+                return Err("source outside the context directory".to_string());
+            }
+            read_source(root, path).ok_or_else(|| "cannot read source".to_string())?
+        }
+    };
+    Ok(format!("{:x}", Sha256::digest(&content)) == expected)
+}
+
+/// The file at `path` under `root`, unless it resolves (through symlinks)
+/// outside it. Why a read failed is not reported back to the client.
+fn read_source(root: &Path, path: &Path) -> Option<Vec<u8>> {
+    let root = root.canonicalize().ok()?;
+    let file = root.join(path).canonicalize().ok()?;
+    if !file.starts_with(&root) {
+        return None;
+    }
+    std::fs::read(file).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn verdicts_cover_provenance_expiry_and_drift() {
+        let now = Utc::now().to_rfc3339();
+        let hello = format!("sha256:{:x}", Sha256::digest(b"hello"));
+        let items = json!([
+            {"id": "a", "src": "inline", "ts": now, "ttl": 60, "hash": hello, "content": "hello"},
+            {"id": "b", "src": "inline", "ts": now, "ttl": 60, "hash": hello, "content": "changed"},
+            {"id": "c", "src": "inline", "ts": "2020-01-01T00:00:00Z", "ttl": 10, "hash": hello},
+            {"id": "d", "ts": now, "ttl": 60, "hash": hello},
+            {"id": "e", "src": "https://example.com", "ts": now, "ttl": 60, "hash": hello},
+            {"id": "f", "src": "inline", "ts": now, "ttl": -1, "hash": hello}
+        ]);
+        let verdicts: Vec<Verdict> = assess(Some(&items)).iter().map(|v| v.verdict).collect();
+        assert_eq!(
+            verdicts,
+            [
+                Verdict::Fresh,
+                Verdict::Drifted,
+                Verdict::Expired,
+                Verdict::Rejected,
+                Verdict::Unverified,
+                Verdict::Rejected
+            ]
+        );
+    }
+
+    #[test]
+    fn file_sources_are_read_only_from_the_context_directory() {
+        let base = std::env::temp_dir().join(format!("context-{}", uuid::Uuid::new_v4()));
+        let root = base.join("context");
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+        std::fs::write(root.join("notes/a.md"), "hello").unwrap();
+        std::fs::write(base.join("secret.txt"), "hello").unwrap();
+        let item = |src: &str| ContextItem {
+            id: "x".into(),
+            src: src.into(),
+            ts: Utc::now(),
+            ttl: 60,
+            hash: format!("sha256:{:x}", Sha256::digest(b"hello")),
+            content: None,
+        };
+
+        assert_eq!(check_hash_in(&item("notes/a.md"), &root), Ok(true));
+        assert_eq!(check_hash_in(&item("file://notes/a.md"), &root), Ok(true));
+        let outside = Err("source outside the context directory".to_string());
+        assert_eq!(check_hash_in(&item("../secret.txt"), &root), outside);
+        assert_eq!(check_hash_in(&item("/etc/hostname"), &root), outside);
+        let unreadable = Err("cannot read source".to_string());
+        assert_eq!(check_hash_in(&item("notes/missing.md"), &root), unreadable);
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(base.join("secret.txt"), root.join("link")).unwrap();
+            assert_eq!(check_hash_in(&item("link"), &root), unreadable);
+        }
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
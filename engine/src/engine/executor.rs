@@ -1,12 +1,14 @@
 use super::types::Policy;
-use anyhow::{anyhow, Context};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Action {
     Cli(String),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecResult {
     pub ok: bool,
     pub drift: bool,
@@ -16,15 +18,11 @@ pub struct ExecResult {
 pub async fn execute(action: Action, _policy: &Policy) -> anyhow::Result<ExecResult> {
     match action {
         Action::Cli(cmd) => {
-            // Capability gate (simple heuristic). If STRICT_CAPS=1, block risky ops.
-            if let Some(cap) = detect_capability(&cmd) {
-                if std::env::var("STRICT_CAPS").ok().as_deref() == Some("1") {
-                    return Err(anyhow!("capability gate blocked: {}", cap));
-                }
-            }
             let out = Command::new("bash")
                 .arg("-lc")
                 .arg(&cmd)
+                // Dropping the future (e.g. a cancelled job) must not leave the child behind.
+                .kill_on_drop(true)
                 .output()
                 .await
                 .with_context(|| format!("failed to spawn: {}", cmd))?;
@@ -38,6 +36,13 @@ pub async fn execute(action: Action, _policy: &Policy) -> anyhow::Result<ExecRes
     }
 }
 
+/// Capability an action would exercise (simple heuristic), if any.
+pub fn capability_for(action: &Action) -> Option<&'static str> {
+    match action {
+        Action::Cli(cmd) => detect_capability(cmd),
+    }
+}
+
 fn detect_capability(cmd: &str) -> Option<&'static str> {
     let s = cmd.to_lowercase();
     if s.contains("curl ") || s.contains("wget ") {
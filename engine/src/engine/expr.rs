@@ -0,0 +1,412 @@
+//! Gate predicates from policy files, e.g. `A==1 && P==1 && Δ==0` or
+//! `U<τ || E==0`. The language is deliberately small: numbers, booleans,
+//! identifiers, `!`, unary `-`, `* /`, `+ -`, comparisons, `&&`, `||` and
+//! parentheses. There are no calls or assignments, and input size and
+//! nesting are bounded, so evaluating a policy cannot do anything but
+//! compute a value.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const MAX_SOURCE_LEN: usize = 1024;
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Num(f64),
+    Bool(bool),
+}
+
+impl Value {
+    fn truthy(self) -> bool {
+        match self {
+            Value::Bool(b) => b,
+            Value::Num(n) => n != 0.0,
+        }
+    }
+
+    fn num(self, op: &str) -> anyhow::Result<f64> {
+        match self {
+            Value::Num(n) => Ok(n),
+            Value::Bool(_) => anyhow::bail!("operator {} expects a number, got a boolean", op),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Or => "||",
+            BinOp::And => "&&",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Lit(Value),
+    Ident(String),
+    Not(Box<Node>),
+    Neg(Box<Node>),
+    Bin(BinOp, Box<Node>, Box<Node>),
+}
+
+/// A parsed predicate. Serializes as its source text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expr {
+    source: String,
+    root: Node,
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        if source.len() > MAX_SOURCE_LEN {
+            anyhow::bail!("expression longer than {} bytes", MAX_SOURCE_LEN);
+        }
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let root = parser.or()?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            anyhow::bail!("unexpected {} in `{}`", tok, source);
+        }
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// Identifiers the expression reads, in order of first use.
+    pub fn idents(&self) -> Vec<&str> {
+        fn walk<'a>(node: &'a Node, out: &mut Vec<&'a str>) {
+            match node {
+                Node::Lit(_) => {}
+                Node::Ident(name) => {
+                    if !out.contains(&name.as_str()) {
+                        out.push(name);
+                    }
+                }
+                Node::Not(inner) | Node::Neg(inner) => walk(inner, out),
+                Node::Bin(_, l, r) => {
+                    walk(l, out);
+                    walk(r, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.root, &mut out);
+        out
+    }
+
+    pub fn eval(&self, env: &dyn Fn(&str) -> Option<Value>) -> anyhow::Result<Value> {
+        eval(&self.root, env)
+    }
+
+    /// Evaluate as a predicate; numbers are true when non-zero.
+    pub fn holds(&self, env: &dyn Fn(&str) -> Option<Value>) -> anyhow::Result<bool> {
+        Ok(self.eval(env)?.truthy())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Expr {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> anyhow::Result<Self> {
+        Expr::parse(&source)
+    }
+}
+
+impl From<Expr> for String {
+    fn from(expr: Expr) -> String {
+        expr.source
+    }
+}
+
+fn eval(node: &Node, env: &dyn Fn(&str) -> Option<Value>) -> anyhow::Result<Value> {
+    Ok(match node {
+        Node::Lit(v) => *v,
+        Node::Ident(name) => {
+            env(name).ok_or_else(|| anyhow::anyhow!("unknown identifier `{}`", name))?
+        }
+        Node::Not(inner) => Value::Bool(!eval(inner, env)?.truthy()),
+        Node::Neg(inner) => Value::Num(-eval(inner, env)?.num("-")?),
+        // Short-circuit so `U<τ || E==0` never evaluates the right side needlessly.
+        Node::Bin(BinOp::Or, l, r) => Value::Bool(eval(l, env)?.truthy() || eval(r, env)?.truthy()),
+        Node::Bin(BinOp::And, l, r) => {
+            Value::Bool(eval(l, env)?.truthy() && eval(r, env)?.truthy())
+        }
+        Node::Bin(op, l, r) => {
+            let (l, r) = (eval(l, env)?, eval(r, env)?);
+            match (op, l, r) {
+                (BinOp::Eq, Value::Bool(a), Value::Bool(b)) => Value::Bool(a == b),
+                (BinOp::Ne, Value::Bool(a), Value::Bool(b)) => Value::Bool(a != b),
+                _ => {
+                    let sym = op.symbol();
+                    let (a, b) = (l.num(sym)?, r.num(sym)?);
+                    match op {
+                        BinOp::Eq => Value::Bool(a == b),
+                        BinOp::Ne => Value::Bool(a != b),
+                        BinOp::Lt => Value::Bool(a < b),
+                        BinOp::Le => Value::Bool(a <= b),
+                        BinOp::Gt => Value::Bool(a > b),
+                        BinOp::Ge => Value::Bool(a >= b),
+                        BinOp::Add => Value::Num(a + b),
+                        BinOp::Sub => Value::Num(a - b),
+                        BinOp::Mul => Value::Num(a * b),
+                        BinOp::Div => Value::Num(a / b),
+                        BinOp::Or | BinOp::And => unreachable!(),
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "number {}", n),
+            Token::Ident(s) => write!(f, "identifier `{}`", s),
+            Token::Op(op) => write!(f, "`{}`", op),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+        }
+    }
+}
+
+const OPERATORS: [&str; 15] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "(", ")",
+];
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() || c == '.' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let n = rest[..end]
+                .parse::<f64>()
+                .map_err(|_| anyhow::anyhow!("bad number `{}`", &rest[..end]))?;
+            tokens.push(Token::Num(n));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            // Unicode letters so `Δ` and `τ` are ordinary identifiers.
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(match *op {
+                "(" => Token::LParen,
+                ")" => Token::RParen,
+                op => Token::Op(op),
+            });
+            rest = &rest[op.len()..];
+        } else {
+            anyhow::bail!("unexpected character `{}` in `{}`", c, source);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinOp)],
+        next: fn(&mut Self) -> anyhow::Result<Node>,
+    ) -> anyhow::Result<Node> {
+        let mut lhs = next(self)?;
+        'outer: loop {
+            for (sym, op) in ops {
+                if self.eat(sym) {
+                    let rhs = next(self)?;
+                    lhs = Node::Bin(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn or(&mut self) -> anyhow::Result<Node> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            anyhow::bail!("expression nested deeper than {}", MAX_DEPTH);
+        }
+        let node = self.binary(&[("||", BinOp::Or)], Self::and);
+        self.depth -= 1;
+        node
+    }
+
+    fn and(&mut self) -> anyhow::Result<Node> {
+        self.binary(&[("&&", BinOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> anyhow::Result<Node> {
+        let lhs = self.additive()?;
+        let ops = [
+            ("==", BinOp::Eq),
+            ("!=", BinOp::Ne),
+            ("<=", BinOp::Le),
+            (">=", BinOp::Ge),
+            ("<", BinOp::Lt),
+            (">", BinOp::Gt),
+        ];
+        for (sym, op) in ops {
+            if self.eat(sym) {
+                let rhs = self.additive()?;
+                return Ok(Node::Bin(op, Box::new(lhs), Box::new(rhs)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn additive(&mut self) -> anyhow::Result<Node> {
+        self.binary(&[("+", BinOp::Add), ("-", BinOp::Sub)], Self::term)
+    }
+
+    fn term(&mut self) -> anyhow::Result<Node> {
+        self.binary(&[("*", BinOp::Mul), ("/", BinOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Node> {
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.nested(Self::unary)?)));
+        }
+        if self.eat("-") {
+            return Ok(Node::Neg(Box::new(self.nested(Self::unary)?)));
+        }
+        self.atom()
+    }
+
+    fn nested(&mut self, f: fn(&mut Self) -> anyhow::Result<Node>) -> anyhow::Result<Node> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            anyhow::bail!("expression nested deeper than {}", MAX_DEPTH);
+        }
+        let node = f(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Node> {
+        let Some(tok) = self.tokens.get(self.pos).cloned() else {
+            anyhow::bail!("unexpected end of expression");
+        };
+        self.pos += 1;
+        match tok {
+            Token::Num(n) => Ok(Node::Lit(Value::Num(n))),
+            Token::Ident(name) => Ok(match name.as_str() {
+                "true" => Node::Lit(Value::Bool(true)),
+                "false" => Node::Lit(Value::Bool(false)),
+                _ => Node::Ident(name),
+            }),
+            Token::LParen => {
+                let inner = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    anyhow::bail!("expected `)`");
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            other => anyhow::bail!("unexpected {}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_policy_gates_over_bits() {
+        let env = |name: &str| match name {
+            "A" | "P" => Some(Value::Num(1.0)),
+            "Δ" | "E" => Some(Value::Num(0.0)),
+            "U" => Some(Value::Num(0.9)),
+            "τ" => Some(Value::Num(0.7)),
+            _ => None,
+        };
+        let act_if = Expr::parse("A==1 && P==1 && Δ==0").unwrap();
+        assert!(act_if.holds(&env).unwrap());
+        assert!(Expr::parse("U<τ || E==0").unwrap().holds(&env).unwrap());
+        assert!(!Expr::parse("!(U - 0.2 <= τ * 1)")
+            .unwrap()
+            .holds(&env)
+            .unwrap());
+        assert_eq!(
+            Expr::parse("U<τ || E==0").unwrap().idents(),
+            ["U", "τ", "E"]
+        );
+
+        assert!(Expr::parse("A==1 &&").is_err());
+        assert!(Expr::parse("A==1; rm").is_err());
+        assert!(Expr::parse(&"(".repeat(100)).is_err());
+        assert!(Expr::parse("X==1").unwrap().holds(&env).is_err());
+    }
+}
@@ -2,36 +2,99 @@ use anyhow::Result;
 use serde_json::{json, Value};
 use std::fs;
 
-use crate::engine::openai::chat_json;
+use crate::engine::openai::{chat_json, chat_json_stream};
+use crate::engine::sandbox::unit;
 
-pub async fn handle(user_msg: &str) -> Result<Value> {
-    let system = fs::read_to_string("prompts/META_OMNI.md").unwrap_or_else(|_| {
+fn system_prompt() -> String {
+    fs::read_to_string("prompts/META_OMNI.md").unwrap_or_else(|_| {
         "You are One Engine v0.2. Respond with JSON containing a 'reply' field.".to_string()
+    })
+}
+
+fn fallback(user_msg: &str) -> Value {
+    let reply = match user_msg.to_lowercase().as_str() {
+        msg if msg.contains("who am i") => "I am One Engine v0.2, a metacognitive AI system with self-awareness capabilities.",
+        msg if msg.contains("hello") => "Hello! I'm One Engine, ready to assist with metacognitive validation and adaptive control.",
+        msg if msg.contains("help") => "I can process tasks with uncertainty tracking, trust calibration, and failure awareness.",
+        _ => "I'm processing your request with metacognitive awareness. How can I help you today?"
+    };
+    json!({
+        "reply": reply,
+        "bits": {"A":1,"U":0,"P":1,"E":0,"Δ":0,"I":0,"R":0,"T":1,"M":0},
+        "intent": {"goal":"chat","constraints":[],"evidence":["fallback response"]},
+        "patch": null,
+        "explanation": {"assumptions":["OpenAI unavailable"],"evidence":["fallback mode"],"limits":["no LM processing"]}
+    })
+}
+
+/// Seeded stand-in for the LM, used by sandboxed validation runs: the
+/// fallback reply with bits drawn from `seed` and the message.
+pub fn mock(user_msg: &str, seed: u64) -> Value {
+    let draw = |salt| (unit(seed, user_msg, salt) * 100.0).round() / 100.0;
+    let u = draw(0);
+    let mut out = fallback(user_msg);
+    out["bits"] = json!({
+        "A": 1, "U": u, "P": 1, "E": if draw(1) < u / 2.0 { 1 } else { 0 },
+        "Δ": 0, "I": 0, "R": 0, "T": ((1.0 - u) * 100.0).round() / 100.0, "M": 0
     });
+    out["explanation"]["assumptions"] = json!(["mock LM", format!("seed {}", seed)]);
+    guard(out)
+}
+
+pub async fn handle(user_msg: &str) -> Result<Value> {
+    let system = system_prompt();
 
     // Try OpenAI, fallback to simple response on error
     let out = match chat_json(&system, user_msg).await {
         Ok(response) => response,
         Err(e) => {
             eprintln!("OpenAI error: {}", e);
-            // Fallback response
-            let reply = match user_msg.to_lowercase().as_str() {
-                msg if msg.contains("who am i") => "I am One Engine v0.2, a metacognitive AI system with self-awareness capabilities.",
-                msg if msg.contains("hello") => "Hello! I'm One Engine, ready to assist with metacognitive validation and adaptive control.",
-                msg if msg.contains("help") => "I can process tasks with uncertainty tracking, trust calibration, and failure awareness.",
-                _ => "I'm processing your request with metacognitive awareness. How can I help you today?"
-            };
-            json!({
-                "reply": reply,
-                "bits": {"A":1,"U":0,"P":1,"E":0,"Δ":0,"I":0,"R":0,"T":1,"M":0},
-                "intent": {"goal":"chat","constraints":[],"evidence":["fallback response"]},
-                "patch": null,
-                "explanation": {"assumptions":["OpenAI unavailable"],"evidence":["fallback mode"],"limits":["no LM processing"]}
-            })
+            fallback(user_msg)
         }
     };
+    Ok(guard(out))
+}
+
+/// Like [`handle`], but streams the `reply` text to `on_reply` while the LM
+/// is still generating. The fallback reply is streamed word by word so
+/// clients see the same frames either way; if the LM failed after part of
+/// its reply went out, `on_reset` is called first so clients drop it.
+pub async fn handle_streaming(
+    user_msg: &str,
+    mut on_reply: impl FnMut(&str),
+    mut on_reset: impl FnMut(&str),
+) -> Result<Value> {
+    let system = system_prompt();
+    let mut reply = ReplyExtractor::default();
+    let mut sent = false;
+    let out = match chat_json_stream(&system, user_msg, |delta| {
+        let text = reply.feed(delta);
+        if !text.is_empty() {
+            sent = true;
+            on_reply(&text);
+        }
+    })
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("OpenAI error: {}", e);
+            if sent {
+                on_reset("LM stream failed; replaced by the fallback reply");
+            }
+            let out = fallback(user_msg);
+            let text = out.get("reply").and_then(|v| v.as_str()).unwrap_or("");
+            for word in text.split_inclusive(' ') {
+                on_reply(word);
+            }
+            out
+        }
+    };
+    Ok(guard(out))
+}
 
-    // Hard guards: ensure required fields present
+// Hard guards: ensure required fields present
+fn guard(out: Value) -> Value {
     let bits = out
         .get("bits")
         .cloned()
@@ -50,11 +113,161 @@ pub async fn handle(user_msg: &str) -> Result<Value> {
         .cloned()
         .unwrap_or(json!({"assumptions":[],"evidence":[],"limits":[]}));
 
-    Ok(json!({
+    json!({
       "intent": intent,
       "bits": bits,
       "patch": patch,
       "explanation": explanation,
       "manifest": { "evidence": { "reply": reply } }
-    }))
+    })
+}
+
+/// Pulls the decoded value of the top-level `"reply"` string out of a JSON
+/// object that arrives in arbitrary fragments.
+#[derive(Default)]
+struct ReplyExtractor {
+    scan: Scanner,
+    state: ReplyState,
+}
+
+/// Just enough JSON structure to tell the top-level object's keys from
+/// nested keys and string contents.
+#[derive(Default)]
+struct Scanner {
+    depth: usize,
+    /// Raw contents of the string being read, if inside one.
+    string: Option<String>,
+    escaped: bool,
+    /// The last top-level key read, until its value starts.
+    key: Option<String>,
+    after_colon: bool,
+}
+
+impl Scanner {
+    /// Consume `c`; true when it opens the top-level `reply` string.
+    fn opens_reply(&mut self, c: char) -> bool {
+        if let Some(s) = &mut self.string {
+            if self.escaped {
+                self.escaped = false;
+                s.push(c);
+            } else if c == '\\' {
+                self.escaped = true;
+                s.push(c);
+            } else if c == '"' {
+                let s = self.string.take();
+                // A top-level string not following a colon is a key.
+                self.key = s.filter(|_| self.depth == 1 && !self.after_colon);
+                self.after_colon = false;
+            } else {
+                s.push(c);
+            }
+            return false;
+        }
+        match c {
+            '"' if self.depth == 1 && self.after_colon && self.key.as_deref() == Some("reply") => {
+                return true;
+            }
+            '"' => self.string = Some(String::new()),
+            ':' if self.depth == 1 && self.key.is_some() => self.after_colon = true,
+            c if c.is_whitespace() => {}
+            c => {
+                match c {
+                    '{' | '[' => self.depth += 1,
+                    '}' | ']' => self.depth = self.depth.saturating_sub(1),
+                    _ => {}
+                }
+                self.key = None;
+                self.after_colon = false;
+            }
+        }
+        false
+    }
+}
+
+#[derive(Default, PartialEq)]
+enum ReplyState {
+    #[default]
+    Searching,
+    Inside {
+        escape: Option<String>,
+    },
+    Done,
+}
+
+impl ReplyExtractor {
+    fn feed(&mut self, delta: &str) -> String {
+        let mut out = String::new();
+        for c in delta.chars() {
+            match &mut self.state {
+                ReplyState::Searching => {
+                    if self.scan.opens_reply(c) {
+                        self.state = ReplyState::Inside { escape: None };
+                    }
+                }
+                ReplyState::Inside { escape: None } => match c {
+                    '"' => self.state = ReplyState::Done,
+                    '\\' => {
+                        self.state = ReplyState::Inside {
+                            escape: Some(String::new()),
+                        }
+                    }
+                    _ => out.push(c),
+                },
+                ReplyState::Inside { escape: Some(seq) } => {
+                    seq.push(c);
+                    let decoded = match seq.as_str() {
+                        "n" => Some("\n".to_string()),
+                        "t" => Some("\t".to_string()),
+                        "r" => Some("\r".to_string()),
+                        s if s.starts_with('u') && s.len() == 5 => Some(
+                            u32::from_str_radix(&s[1..], 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .map(String::from)
+                                .unwrap_or_default(),
+                        ),
+                        s if s.starts_with('u') => None,
+                        s => Some(s.to_string()),
+                    };
+                    if let Some(d) = decoded {
+                        out.push_str(&d);
+                        self.state = ReplyState::Inside { escape: None };
+                    }
+                }
+                ReplyState::Done => break,
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_extractor_decodes_fragmented_reply() {
+        let mut x = ReplyExtractor::default();
+        let chunks = [
+            "{\"bits\":{\"A\":1},\"re",
+            "ply\" : \"Hi\\n",
+            "th\\u00e9re \\\"x\\\"",
+            "\",\"patch\":null}",
+        ];
+        let text: String = chunks.iter().map(|c| x.feed(c)).collect();
+        assert_eq!(text, "Hi\nthére \"x\"");
+    }
+
+    #[test]
+    fn reply_extractor_ignores_nested_and_quoted_reply_keys() {
+        let mut x = ReplyExtractor::default();
+        let json = concat!(
+            r#"{"intent":{"goal":"say \"reply\": \"no\"","reply":"nested"},"#,
+            r#""evidence":["reply",{"reply":"in array"}],"#,
+            r#""reply" : "top""#,
+            r#","patch":{"reply":"after"}}"#,
+        );
+        let text: String = json.chars().map(|c| x.feed(&c.to_string())).collect();
+        assert_eq!(text, "top");
+    }
 }
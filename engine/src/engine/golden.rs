@@ -1,16 +1,54 @@
-use crate::engine::bits::Bits as RuntimeBits;
-use anyhow::Result;
+use crate::engine::{
+    self, assertion,
+    bits::Bits as RuntimeBits,
+    kernel::{ExtendedBits, Gates, KernelLoop},
+    sandbox::Sandbox,
+    tape::{Tape, TapeEntry},
+    types::{Policy, RunCtx},
+};
+use anyhow::{bail, Result};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
 use utoipa::ToSchema;
 
+/// Numbers closer than this compare equal when replaying.
+const REPLAY_TOLERANCE: f64 = 1e-4;
+
 #[derive(Debug, Deserialize, Serialize, Clone, JsonSchema, ToSchema)]
 pub struct GoldenCaseRaw {
     pub test: String,
     pub assertion: Value, // allow string or object
     pub result: Value,
     pub bits: Value,
+    /// Set on recorded cases: what [`replay_golden`] needs to re-run them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<Value>,
+}
+
+/// A live run captured as a golden case: its request plus every LM
+/// response and executor result it saw, in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub goal_id: String,
+    pub inputs: Value,
+    pub policy: Policy,
+    pub tape: Vec<TapeEntry>,
+    /// The live kernel when recorded. Record and replay both run in a
+    /// sandbox started from it (gates re-loaded from the contract on
+    /// replay), so neither touches live state and replays do not depend on
+    /// live history. Older recordings replay from the default kernel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<KernelLoop>,
+    /// JSON pointers into the manifest left out of the replay diff.
+    #[serde(default = "default_ignore")]
+    pub ignore: Vec<String>,
+}
+
+fn default_ignore() -> Vec<String> {
+    vec!["/evidence/l2_params".to_string()]
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, ToSchema)]
@@ -87,21 +125,19 @@ fn bits_valid(b: &RuntimeBits) -> bool {
 }
 
 pub async fn validate_golden(name: &str) -> Result<GoldenSummary> {
-    let path = format!("trace/golden/{}.json", name);
-    let s = tokio::fs::read_to_string(&path).await?;
-    let raw: Vec<GoldenCaseRaw> = serde_json::from_str(&s)?;
+    let raw = load_cases(name).await?;
 
     let mut details = Vec::new();
     let mut passed = 0usize;
     for case in raw.into_iter() {
-        let ok_bits = match parse_bits(&case.bits) {
-            Some(b) => bits_valid(&b),
-            None => false,
-        };
-        let (ok, reason) = if ok_bits {
-            (true, None)
-        } else {
-            (false, Some("invalid or out-of-range bits".to_string()))
+        let (ok, reason) = match parse_bits(&case.bits) {
+            Some(b) if bits_valid(&b) => {
+                match assertion::check(&case.assertion, &case.result, &b) {
+                    Ok(()) => (true, None),
+                    Err(failures) => (false, Some(failures.join("; "))),
+                }
+            }
+            _ => (false, Some("invalid or out-of-range bits".to_string())),
         };
         if ok {
             passed += 1;
@@ -149,6 +185,229 @@ pub async fn validate_golden(name: &str) -> Result<GoldenSummary> {
     })
 }
 
+fn golden_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        bail!("invalid golden name: {:?}", name);
+    }
+    Ok(PathBuf::from(format!("trace/golden/{}.json", name)))
+}
+
+async fn load_cases(name: &str) -> Result<Vec<GoldenCaseRaw>> {
+    let s = tokio::fs::read_to_string(golden_path(name)?).await?;
+    Ok(serde_json::from_str(&s)?)
+}
+
+/// Bits in the golden file shape (`A`…`M`, drift as `Δ`).
+fn golden_bits(b: &ExtendedBits) -> Value {
+    json!({
+        "A": b.a, "U": b.u, "P": b.p, "E": b.e, "Δ": b.d,
+        "I": b.i, "R": b.r, "T": b.t, "M": b.m,
+    })
+}
+
+/// Run `goal_id` for real (in a sandbox of the live kernel) while recording
+/// its LM and executor traffic, and store the outcome as case `test` of
+/// golden file `name` (replacing any case of that name).
+pub async fn record_golden(
+    name: &str,
+    test: &str,
+    goal_id: &str,
+    inputs: Value,
+    policy: &Policy,
+) -> Result<GoldenCaseRaw> {
+    let path = golden_path(name)?;
+    let tape = Arc::new(Tape::record());
+    let kernel = engine::kernel_snapshot().await;
+    let mut ctx = RunCtx::new(None);
+    ctx.tape = Some(tape.clone());
+    ctx.sandbox = Some(Arc::new(Sandbox::new(kernel.clone(), 0)));
+    let (manifest, bits, _) = engine::run(&ctx, goal_id, inputs.clone(), policy).await?;
+
+    let recording = Recording {
+        goal_id: goal_id.to_string(),
+        inputs,
+        policy: policy.clone(),
+        tape: tape.entries(),
+        kernel: Some(kernel),
+        ignore: default_ignore(),
+    };
+    let case = GoldenCaseRaw {
+        test: test.to_string(),
+        assertion: json!({"replay": "bits and manifest match the recording"}),
+        result: serde_json::to_value(&manifest)?,
+        bits: golden_bits(&bits),
+        recording: Some(serde_json::to_value(&recording)?),
+    };
+
+    let mut cases = match tokio::fs::read_to_string(&path).await {
+        Ok(s) => serde_json::from_str::<Vec<GoldenCaseRaw>>(&s)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    match cases.iter_mut().find(|c| c.test == test) {
+        Some(existing) => *existing = case.clone(),
+        None => cases.push(case.clone()),
+    }
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(&cases)?).await?;
+    tokio::fs::rename(&tmp, &path).await?;
+    Ok(case)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, ToSchema)]
+pub struct ReplayCase {
+    pub test: String,
+    pub ok: bool,
+    pub reason: Option<String>,
+    /// One line per differing field, as `<json pointer>: recorded → replayed`.
+    pub diffs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, ToSchema)]
+pub struct ReplaySummary {
+    pub name: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Hand-written cases without a recording.
+    pub skipped: usize,
+    pub details: Vec<ReplayCase>,
+}
+
+/// Re-run every recorded case of golden file `name` against its recorded
+/// responses (no network, no processes) and diff bits and manifest.
+pub async fn replay_golden(name: &str) -> Result<ReplaySummary> {
+    let cases = load_cases(name).await?;
+    let mut details = Vec::new();
+    let mut skipped = 0;
+    for case in cases {
+        let Some(recording) = case.recording else {
+            skipped += 1;
+            continue;
+        };
+        let replayed = match serde_json::from_value::<Recording>(recording) {
+            Ok(recording) => replay_case(&case.test, recording, &case.result, &case.bits).await,
+            Err(e) => ReplayCase {
+                test: case.test.clone(),
+                ok: false,
+                reason: Some(format!("invalid recording: {}", e)),
+                diffs: Vec::new(),
+            },
+        };
+        details.push(replayed);
+    }
+    let total = details.len();
+    let passed = details.iter().filter(|c| c.ok).count();
+    Ok(ReplaySummary {
+        name: name.to_string(),
+        total,
+        passed,
+        failed: total - passed,
+        skipped,
+        details,
+    })
+}
+
+async fn replay_case(test: &str, recording: Recording, result: &Value, bits: &Value) -> ReplayCase {
+    let tape = Arc::new(Tape::replay(recording.tape));
+    let mut kernel = recording.kernel.unwrap_or_else(KernelLoop::new);
+    // Today's gate logic is what is under test, not the recorded one.
+    kernel.gates = Gates::load();
+    let mut ctx = RunCtx::new(None);
+    ctx.tape = Some(tape.clone());
+    ctx.sandbox = Some(Arc::new(Sandbox::new(kernel, 0)));
+    let outcome = engine::run(
+        &ctx,
+        &recording.goal_id,
+        recording.inputs,
+        &recording.policy,
+    )
+    .await;
+    let (manifest, replayed_bits) = match outcome {
+        Ok((manifest, replayed_bits, _)) => (manifest, replayed_bits),
+        Err(e) => {
+            return ReplayCase {
+                test: test.to_string(),
+                ok: false,
+                reason: Some(e.to_string()),
+                diffs: Vec::new(),
+            }
+        }
+    };
+
+    let mut diffs = Vec::new();
+    diff_json("/bits", bits, &golden_bits(&replayed_bits), &[], &mut diffs);
+    let mut ignore = recording.ignore;
+    // The manifest repeats the bits already compared above.
+    ignore.extend(["/run_id".to_string(), "/bits".to_string()]);
+    let manifest = serde_json::to_value(&manifest).unwrap_or_default();
+    diff_json("", result, &manifest, &ignore, &mut diffs);
+    let unused = tape.remaining();
+    if unused > 0 {
+        diffs.push(format!(
+            "/tape: {} recorded interaction(s) never replayed",
+            unused
+        ));
+    }
+    ReplayCase {
+        test: test.to_string(),
+        ok: diffs.is_empty(),
+        reason: (!diffs.is_empty())
+            .then(|| format!("{} difference(s) from the recording", diffs.len())),
+        diffs,
+    }
+}
+
+fn diff_json(
+    path: &str,
+    expected: &Value,
+    actual: &Value,
+    ignore: &[String],
+    out: &mut Vec<String>,
+) {
+    if ignore.iter().any(|p| p == path) {
+        return;
+    }
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            let mut keys: Vec<&String> = e.keys().chain(a.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                let (e, a) = (
+                    e.get(key).unwrap_or(&Value::Null),
+                    a.get(key).unwrap_or(&Value::Null),
+                );
+                diff_json(&child, e, a, ignore, out);
+            }
+        }
+        (Value::Array(e), Value::Array(a)) if e.len() == a.len() => {
+            for (i, (e, a)) in e.iter().zip(a).enumerate() {
+                diff_json(&format!("{}/{}", path, i), e, a, ignore, out);
+            }
+        }
+        (Value::Number(e), Value::Number(a)) => {
+            let (e, a) = (
+                e.as_f64().unwrap_or(f64::NAN),
+                a.as_f64().unwrap_or(f64::NAN),
+            );
+            if (e - a).abs() > REPLAY_TOLERANCE || e.is_nan() || a.is_nan() {
+                out.push(format!("{}: {} → {}", path, e, a));
+            }
+        }
+        (e, a) if e != a => out.push(format!("{}: {} → {}", path, e, a)),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,4 +418,14 @@ mod tests {
         assert_eq!(summary.failed, 0, "golden cases should have valid bits");
         assert!(summary.total >= 1);
     }
+
+    #[test]
+    fn diff_json_reports_pointers_and_honours_ignore() {
+        let recorded = json!({"run_id": "a", "bits": {"u": 0.1}, "evidence": {"stdout": "hi", "l2_params": 1}});
+        let replayed = json!({"run_id": "b", "bits": {"u": 0.10001}, "evidence": {"stdout": "bye", "l2_params": 2}});
+        let mut diffs = Vec::new();
+        let ignore = ["/run_id".to_string(), "/evidence/l2_params".to_string()];
+        diff_json("", &recorded, &replayed, &ignore, &mut diffs);
+        assert_eq!(diffs, ["/evidence/stdout: \"hi\" → \"bye\""]);
+    }
 }
@@ -0,0 +1,463 @@
+//! Validation history: every `/validate` run is kept as
+//! `<VALIDATION_STORE>/<id>.json` (default `trace/validation/`) with the
+//! engine version and L2 params it ran under, so runs and versions can be
+//! compared task by task.
+
+use super::kernel::L2Params;
+use super::stats::{self, Summary};
+use crate::api::{ValidateResp, ValidationResult, VersionInfo};
+use anyhow::Context;
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+pub const DEFAULT_ALPHA: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationRecord {
+    pub id: String,
+    pub suite: String,
+    pub created_at: String,
+    pub version: VersionInfo,
+    pub l2_params: L2Params,
+    pub response: ValidateResp,
+}
+
+/// One line of `GET /validate/history`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub suite: String,
+    pub created_at: String,
+    pub version: VersionInfo,
+    pub metacognitive_score: f32,
+    pub brier: f32,
+    pub ece: f32,
+    pub runs: usize,
+}
+
+impl From<&ValidationRecord> for HistoryEntry {
+    fn from(r: &ValidationRecord) -> Self {
+        Self {
+            id: r.id.clone(),
+            suite: r.suite.clone(),
+            created_at: r.created_at.clone(),
+            version: r.version.clone(),
+            metacognitive_score: r.response.metacognitive_score,
+            brier: r.response.calibration.brier,
+            ece: r.response.calibration.ece,
+            runs: r.response.results.len(),
+        }
+    }
+}
+
+pub fn store_dir() -> PathBuf {
+    PathBuf::from(
+        std::env::var("VALIDATION_STORE").unwrap_or_else(|_| "trace/validation".to_string()),
+    )
+}
+
+/// Store a finished validation run; returns its id.
+pub async fn save(
+    suite: &str,
+    l2_params: L2Params,
+    response: &ValidateResp,
+) -> anyhow::Result<String> {
+    let now = Utc::now();
+    let id = format!(
+        "v-{}-{}",
+        now.format("%Y%m%dT%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let record = ValidationRecord {
+        id: id.clone(),
+        suite: suite.to_string(),
+        created_at: now.to_rfc3339(),
+        version: VersionInfo::current(),
+        l2_params,
+        response: response.clone(),
+    };
+    let dir = store_dir();
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(format!("{}.json", id));
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(&record)?).await?;
+    tokio::fs::rename(&tmp, &path).await?;
+    Ok(id)
+}
+
+/// Every stored run, oldest first. Unreadable files are skipped.
+pub async fn list() -> anyhow::Result<Vec<ValidationRecord>> {
+    let dir = store_dir();
+    let mut records = Vec::new();
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(records),
+        Err(e) => return Err(e).with_context(|| format!("cannot read {}", dir.display())),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = tokio::fs::read(&path)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(serde_json::from_slice::<ValidationRecord>(&bytes)?));
+        match parsed {
+            Ok(record) => records.push(record),
+            Err(e) => tracing::warn!("Skipping validation record {}: {}", path.display(), e),
+        }
+    }
+    records.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(records)
+}
+
+pub async fn get(id: &str) -> anyhow::Result<Option<ValidationRecord>> {
+    Ok(list().await?.into_iter().find(|r| r.id == id))
+}
+
+/// Runs picked by `selector`: a run id, or every run of an engine version,
+/// git ref or build token (optionally of one suite).
+pub fn select<'a>(
+    records: &'a [ValidationRecord],
+    selector: &str,
+    suite: Option<&str>,
+) -> Vec<&'a ValidationRecord> {
+    if let Some(record) = records.iter().find(|r| r.id == selector) {
+        return vec![record];
+    }
+    records
+        .iter()
+        .filter(|r| suite.is_none_or(|s| r.suite == s))
+        .filter(|r| {
+            r.version.engine == selector
+                || r.version.git_ref.as_deref() == Some(selector)
+                || r.version.build_token.as_deref() == Some(selector)
+        })
+        .collect()
+}
+
+/// A metric compared between two sets of runs. `regressed` means head is
+/// worse than base with p < alpha.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct MetricDelta {
+    pub base: Summary,
+    pub head: Summary,
+    /// head − base.
+    pub delta: f64,
+    /// Welch's t-test; `None` with fewer than two values on a side.
+    pub p_value: Option<f64>,
+    pub regressed: bool,
+}
+
+impl MetricDelta {
+    fn new(base: &[f64], head: &[f64], higher_is_better: bool, alpha: f64) -> Self {
+        let (b, h) = (stats::summarize(base), stats::summarize(head));
+        let delta = h.mean - b.mean;
+        let p_value = stats::welch_p(base, head);
+        let worse = if higher_is_better {
+            delta < 0.0
+        } else {
+            delta > 0.0
+        };
+        Self {
+            base: b,
+            head: h,
+            delta,
+            p_value,
+            regressed: worse && p_value.is_some_and(|p| p < alpha),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TaskDelta {
+    pub task: String,
+    pub score: MetricDelta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Comparison {
+    pub base: Vec<String>,
+    pub head: Vec<String>,
+    pub alpha: f64,
+    /// Metacognitive score over every run of a task run on both sides.
+    pub score: MetricDelta,
+    /// Per-run squared error (T − success)², i.e. the Brier score, over the
+    /// same runs as `score`.
+    pub brier: MetricDelta,
+    /// Per validation run of a suite run on both sides; needs two runs a
+    /// side for a p-value.
+    pub ece: MetricDelta,
+    /// Tasks run on both sides.
+    pub tasks: Vec<TaskDelta>,
+    /// Tasks run only in base or only in head; left out of every metric.
+    pub base_only: Vec<String>,
+    pub head_only: Vec<String>,
+    /// Human-readable list of everything that regressed.
+    pub regressions: Vec<String>,
+    /// Metrics and tasks with fewer than two values on a side: they have
+    /// no p-value and can never be flagged. Compare versions, or runs with
+    /// `repeats` ≥ 2, to test them.
+    pub insufficient_samples: Vec<String>,
+}
+
+/// Compares only what both sides ran, so selectors spanning different
+/// suites or task sets don't pool unlike results.
+pub fn compare(base: &[&ValidationRecord], head: &[&ValidationRecord], alpha: f64) -> Comparison {
+    let task_names = |runs: &[&ValidationRecord]| -> BTreeSet<String> {
+        runs.iter()
+            .flat_map(|r| r.response.results.iter().map(|res| res.task.clone()))
+            .collect()
+    };
+    let (base_names, head_names) = (task_names(base), task_names(head));
+    let shared: BTreeSet<&String> = base_names.intersection(&head_names).collect();
+    let only = |names: &BTreeSet<String>| {
+        names
+            .iter()
+            .filter(|t| !shared.contains(t))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let (base_only, head_only) = (only(&base_names), only(&head_names));
+
+    let results = |runs: &[&ValidationRecord]| -> Vec<ValidationResult> {
+        runs.iter()
+            .flat_map(|r| r.response.results.iter())
+            .filter(|r| shared.contains(&r.task))
+            .cloned()
+            .collect()
+    };
+    let (base_results, head_results) = (results(base), results(head));
+    let scores = |rs: &[ValidationResult]| rs.iter().map(|r| r.score as f64).collect::<Vec<_>>();
+    let squared_errors = |rs: &[ValidationResult]| {
+        rs.iter()
+            .map(|r| {
+                let success = if r.actual_bits.e == 0.0 { 1.0 } else { 0.0 };
+                (r.actual_bits.t.clamp(0.0, 1.0) as f64 - success).powi(2)
+            })
+            .collect::<Vec<_>>()
+    };
+    let suites = |runs: &[&ValidationRecord]| -> BTreeSet<String> {
+        runs.iter().map(|r| r.suite.clone()).collect()
+    };
+    let (base_suites, head_suites) = (suites(base), suites(head));
+    let eces = |runs: &[&ValidationRecord]| {
+        runs.iter()
+            .filter(|r| base_suites.contains(&r.suite) && head_suites.contains(&r.suite))
+            .map(|r| r.response.calibration.ece as f64)
+            .collect::<Vec<_>>()
+    };
+
+    let by_task = |rs: &[ValidationResult]| {
+        let mut map: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for r in rs {
+            map.entry(r.task.clone()).or_default().push(r.score as f64);
+        }
+        map
+    };
+    let (base_tasks, head_tasks) = (by_task(&base_results), by_task(&head_results));
+    let tasks: Vec<TaskDelta> = base_tasks
+        .iter()
+        .filter_map(|(task, b)| {
+            head_tasks.get(task).map(|h| TaskDelta {
+                task: task.clone(),
+                score: MetricDelta::new(b, h, true, alpha),
+            })
+        })
+        .collect();
+
+    let score = MetricDelta::new(&scores(&base_results), &scores(&head_results), true, alpha);
+    let brier = MetricDelta::new(
+        &squared_errors(&base_results),
+        &squared_errors(&head_results),
+        false,
+        alpha,
+    );
+    let ece = MetricDelta::new(&eces(base), &eces(head), false, alpha);
+
+    let mut regressions = Vec::new();
+    let mut insufficient_samples = Vec::new();
+    let metrics = [
+        ("score".to_string(), &score),
+        ("brier".to_string(), &brier),
+        ("ece".to_string(), &ece),
+    ]
+    .into_iter()
+    .chain(
+        tasks
+            .iter()
+            .map(|t| (format!("{} score", t.task), &t.score)),
+    );
+    for (name, metric) in metrics {
+        if metric.regressed {
+            regressions.push(describe(&name, metric));
+        }
+        if metric.p_value.is_none() {
+            insufficient_samples.push(format!(
+                "{} ({} base, {} head)",
+                name, metric.base.n, metric.head.n
+            ));
+        }
+    }
+
+    Comparison {
+        base: base.iter().map(|r| r.id.clone()).collect(),
+        head: head.iter().map(|r| r.id.clone()).collect(),
+        alpha,
+        score,
+        brier,
+        ece,
+        tasks,
+        base_only,
+        head_only,
+        regressions,
+        insufficient_samples,
+    }
+}
+
+fn describe(name: &str, m: &MetricDelta) -> String {
+    format!(
+        "{}: {:.3} → {:.3} ({:+.3}, p={:.4})",
+        name,
+        m.base.mean,
+        m.head.mean,
+        m.delta,
+        m.p_value.unwrap_or(f64::NAN)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{calibration, kernel::KernelLoop, types::Bits};
+
+    fn record(id: &str, engine: &str, suite: &str, scores: &[(&str, f32)]) -> ValidationRecord {
+        let results = scores
+            .iter()
+            .enumerate()
+            .map(|(i, (task, score))| ValidationResult {
+                task: task.to_string(),
+                expected_difficulty: 0.5,
+                actual_bits: Bits {
+                    a: 1.0,
+                    u: 0.1,
+                    p: 1.0,
+                    e: 0.0,
+                    d: 0.0,
+                    i: 0.0,
+                    r: 0.0,
+                    t: *score,
+                    m: 0.0,
+                },
+                score: *score,
+                repeat: i as u32 + 1,
+                tags: Vec::new(),
+                violations: Vec::new(),
+            })
+            .collect();
+        ValidationRecord {
+            id: id.into(),
+            suite: suite.into(),
+            created_at: Utc::now().to_rfc3339(),
+            version: VersionInfo {
+                engine: engine.into(),
+                build_token: None,
+                git_ref: Some(format!("ref-{}", engine)),
+                ts: Utc::now().to_rfc3339(),
+            },
+            l2_params: KernelLoop::new().l2_params,
+            response: ValidateResp {
+                validation_id: Some(id.into()),
+                metacognitive_score: 0.0,
+                results,
+                summary: String::new(),
+                calibration: calibration::compute(&[], calibration::DEFAULT_BINS),
+                score_ci: Default::default(),
+                aggregates: Vec::new(),
+                seed: 0,
+            },
+        }
+    }
+
+    fn repeated(score: f32) -> Vec<(&'static str, f32)> {
+        [0.0, 0.02, -0.02, 0.01]
+            .iter()
+            .map(|jitter| ("a", score + jitter))
+            .collect()
+    }
+
+    #[test]
+    fn select_by_id_version_ref_and_suite() {
+        let records = vec![
+            record("r1", "0.1.0", "easy", &[("a", 0.9)]),
+            record("r2", "0.1.0", "hard", &[("a", 0.9)]),
+            record("r3", "0.2.0", "easy", &[("a", 0.9)]),
+        ];
+        let ids =
+            |runs: Vec<&ValidationRecord>| runs.iter().map(|r| r.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(select(&records, "r2", Some("easy"))), ["r2"]);
+        assert_eq!(ids(select(&records, "0.1.0", None)), ["r1", "r2"]);
+        assert_eq!(ids(select(&records, "0.1.0", Some("easy"))), ["r1"]);
+        assert_eq!(ids(select(&records, "ref-0.2.0", None)), ["r3"]);
+        assert!(select(&records, "0.3.0", None).is_empty());
+    }
+
+    #[test]
+    fn a_clearly_worse_head_is_flagged_and_an_equal_one_is_not() {
+        let base = record("base", "0.1.0", "easy", &repeated(0.9));
+        let worse = record("worse", "0.2.0", "easy", &repeated(0.3));
+        let compared = compare(&[&base], &[&worse], DEFAULT_ALPHA);
+        assert!(compared.score.regressed);
+        assert!(compared.tasks[0].score.regressed);
+        assert!(compared.regressions.iter().any(|r| r.starts_with("score:")));
+        assert!(compared
+            .regressions
+            .iter()
+            .any(|r| r.starts_with("a score:")));
+
+        let equal = record("equal", "0.2.0", "easy", &repeated(0.9));
+        let compared = compare(&[&base], &[&equal], DEFAULT_ALPHA);
+        assert!(compared.regressions.is_empty());
+        assert_eq!(compared.score.p_value, Some(1.0));
+        // One validation run a side has a single ECE value each.
+        assert_eq!(compared.insufficient_samples, ["ece (1 base, 1 head)"]);
+    }
+
+    #[test]
+    fn overall_metrics_only_cover_tasks_run_on_both_sides() {
+        let base = record("base", "0.1.0", "easy", &repeated(0.9));
+        let easy = record("easy", "0.2.0", "easy", &repeated(0.9));
+        let hard: Vec<_> = repeated(0.1).into_iter().map(|(_, s)| ("h", s)).collect();
+        let hard = record("hard", "0.2.0", "hard", &hard);
+        let compared = compare(&[&base], &[&easy, &hard], DEFAULT_ALPHA);
+        // Pooling `h` into head's score would flag a regression no task has.
+        assert!(compared.regressions.is_empty());
+        assert_eq!((compared.score.base.n, compared.score.head.n), (4, 4));
+        assert_eq!(compared.brier.head.n, 4);
+        assert_eq!(compared.ece.head.n, 1);
+        assert_eq!(compared.tasks.len(), 1);
+        assert!(compared.base_only.is_empty());
+        assert_eq!(compared.head_only, ["h"]);
+    }
+
+    #[test]
+    fn single_runs_are_reported_as_insufficient_samples() {
+        let base = record("base", "0.1.0", "easy", &[("a", 0.9), ("b", 0.9)]);
+        let head = record("head", "0.2.0", "easy", &[("a", 0.1), ("b", 0.1)]);
+        let compared = compare(&[&base], &[&head], DEFAULT_ALPHA);
+        assert!(compared.tasks.iter().all(|t| !t.score.regressed));
+        assert_eq!(
+            compared.insufficient_samples,
+            [
+                "ece (1 base, 1 head)",
+                "a score (1 base, 1 head)",
+                "b score (1 base, 1 head)"
+            ]
+        );
+    }
+}
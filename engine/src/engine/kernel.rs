@@ -1,3 +1,4 @@
+use super::expr::{Expr, Value};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -36,9 +37,85 @@ impl ExtendedBits {
 pub struct KernelLoop {
     pub l2_params: L2Params,
     pub l3_rules: L3Rules,
+    pub gates: Gates,
 }
 
+const KERNEL_CONTRACT: &str = "schemas/KERNEL_CONTRACT.json";
+
+/// Gate predicates over bits and L2 params from the kernel contract. The
+/// policy-level `act_if` lives in the hot-reloaded policy set and is passed
+/// to [`KernelLoop::ask_act_gate`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gates {
+    pub ask_act: Expr,
+    pub evidence: Expr,
+}
+
+impl Gates {
+    /// Identifiers a gate may read; anything else is rejected at load time.
+    const IDENTS: [&'static str; 14] = [
+        "A",
+        "U",
+        "P",
+        "E",
+        "Δ",
+        "I",
+        "R",
+        "T",
+        "M",
+        "τ",
+        "tau",
+        "ask_act_threshold",
+        "backoff_k",
+        "D",
+    ];
+
+    /// Load from the contract, falling back to its documented defaults
+    /// (with a warning) when the file is missing or a predicate is invalid.
+    pub fn load() -> Self {
+        let contract = std::fs::read_to_string(KERNEL_CONTRACT)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Ok(serde_json::from_str::<serde_json::Value>(&s)?));
+        let contract_gate = |name: &str| -> anyhow::Result<String> {
+            let contract = contract.as_ref().map_err(|e| anyhow::anyhow!("{}", e))?;
+            contract
+                .pointer(&format!("/properties/gates/properties/{}/enum/0", name))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .ok_or_else(|| anyhow::anyhow!("no `{}` gate in {}", name, KERNEL_CONTRACT))
+        };
+        Self {
+            ask_act: Self::gate("ask_act", contract_gate("ask_act"), "A>=1 && P>=1 && Δ==0"),
+            evidence: Self::gate("evidence", contract_gate("evidence"), "U<τ || E==0"),
+        }
+    }
+
+    /// Reject predicates reading anything but bits and L2 params.
+    pub fn check_idents(expr: &Expr) -> anyhow::Result<()> {
+        if let Some(bad) = expr
+            .idents()
+            .into_iter()
+            .find(|i| !Self::IDENTS.contains(i))
+        {
+            anyhow::bail!("unknown identifier `{}` in `{}`", bad, expr);
+        }
+        Ok(())
+    }
+
+    fn gate(name: &str, source: anyhow::Result<String>, default: &str) -> Expr {
+        let parsed = source.and_then(|s| {
+            let expr = Expr::parse(&s)?;
+            Self::check_idents(&expr)?;
+            Ok(expr)
+        });
+        parsed.unwrap_or_else(|e| {
+            tracing::warn!("{} gate: {}; using `{}`", name, e, default);
+            Expr::parse(default).expect("built-in gate parses")
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct L2Params {
     pub ask_act_threshold: f32,
     pub confidence_gate_tau: f32,
@@ -69,12 +146,40 @@ impl KernelLoop {
                 weekly_param_delta_max: 0.15,
                 shadow_rollout_pct: 0.2,
             },
+            gates: Gates::load(),
         }
     }
 
+    fn check(&self, name: &str, gate: &Expr, bits: &ExtendedBits) -> bool {
+        let l2 = &self.l2_params;
+        let env = |ident: &str| {
+            let n = match ident {
+                "A" => bits.a,
+                "U" => bits.u,
+                "P" => bits.p,
+                "E" => bits.e,
+                "Δ" | "D" => bits.d,
+                "I" => bits.i,
+                "R" => bits.r,
+                "T" => bits.t,
+                "M" => bits.m,
+                "τ" | "tau" => l2.confidence_gate_tau,
+                "ask_act_threshold" => l2.ask_act_threshold,
+                "backoff_k" => l2.backoff_k as f32,
+                _ => return None,
+            };
+            Some(Value::Num(n as f64))
+        };
+        // A gate that cannot be evaluated stays closed.
+        gate.holds(&env).unwrap_or_else(|e| {
+            tracing::warn!("{} gate `{}` failed to evaluate: {}", name, gate, e);
+            false
+        })
+    }
+
     pub fn enforce_ask_act_gate(&self, bits: &ExtendedBits) -> Result<(), String> {
-        // STRUCTURAL INVARIANT: A>=1 && P>=1 && Δ==0
-        if !(bits.a >= 1.0 && bits.p >= 1.0 && bits.d == 0.0) {
+        // STRUCTURAL INVARIANT: the contract's ask_act gate
+        if !self.check("ask_act", &self.gates.ask_act, bits) {
             return Err(format!(
                 "Ask-Act gate failed: A={:.1}, P={:.1}, Δ={:.1}",
                 bits.a, bits.p, bits.d
@@ -98,17 +203,24 @@ impl KernelLoop {
         Ok(())
     }
 
-    pub fn ask_act_gate(&self, bits: &ExtendedBits) -> bool {
-        bits.a >= 1.0 && bits.p >= 1.0 && bits.d == 0.0
+    pub fn ask_act_gate(&self, act_if: &Expr, bits: &ExtendedBits) -> bool {
+        self.check("act_if", act_if, bits)
     }
 
     pub fn evidence_gate(&self, bits: &ExtendedBits) -> bool {
-        if bits.u >= self.l2_params.confidence_gate_tau {
-            // Require verification mode first
-            false
-        } else {
-            true
-        }
+        // Closed means verification mode first
+        self.check("evidence", &self.gates.evidence, bits)
+    }
+
+    /// The evidence gate before `act_apply` has produced any evidence: E
+    /// reads as 1, so `U<τ || E==0` reduces to `U<τ` and verification
+    /// first still triggers when U≥τ.
+    pub fn evidence_gate_before_act(&self, bits: &ExtendedBits) -> bool {
+        let bits = ExtendedBits {
+            e: 1.0,
+            ..bits.clone()
+        };
+        self.evidence_gate(&bits)
     }
 
     pub fn should_wake_l3(&self, kpi_history: &[f32]) -> bool {
@@ -144,7 +256,7 @@ impl KernelLoop {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct Meta2Proposal {
     pub symptom: String,
     pub hypothesis: String,
@@ -153,7 +265,7 @@ pub struct Meta2Proposal {
     pub rollback_condition: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub enum Meta2Change {
     ConfidenceGate {
         old_tau: f32,
@@ -168,3 +280,35 @@ pub enum Meta2Change {
         new_threshold: f32,
     },
 }
+
+impl Meta2Change {
+    /// `params` with this change applied.
+    pub fn apply(&self, params: &L2Params) -> L2Params {
+        let mut params = params.clone();
+        match *self {
+            Self::ConfidenceGate { new_tau, .. } => params.confidence_gate_tau = new_tau,
+            Self::BackoffStrategy { new_k, .. } => params.backoff_k = new_k,
+            Self::AskActThreshold { new_threshold, .. } => params.ask_act_threshold = new_threshold,
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evidence_gate_closes_before_act_when_uncertain() {
+        let kernel = KernelLoop::new();
+        let mut bits = ExtendedBits::init();
+        bits.u = 0.8;
+        // E is still 0 before acting; that must not open the gate.
+        assert!(!kernel.evidence_gate_before_act(&bits));
+        bits.u = 0.3;
+        assert!(kernel.evidence_gate_before_act(&bits));
+        // After acting, a clean run (E=0) opens it whatever U is.
+        bits.u = 0.8;
+        assert!(kernel.evidence_gate(&bits));
+    }
+}
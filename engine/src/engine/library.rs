@@ -0,0 +1,326 @@
+use super::context::{ContextVerdict, Verdict};
+use chrono::{DateTime, Utc};
+use one_engine::research::{self, search::SearchIndex, watch, ResearchArtifact, ReverifyReport};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OnceCell};
+use utoipa::ToSchema;
+
+const DISK_INDEX: &str = "research/index.jsonl";
+const DEFAULT_REVERIFY_SECS: u64 = 3600;
+const DEFAULT_REFRESH_SECS: u64 = 60;
+
+/// An artifact past its TTL.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StaleArtifact {
+    pub artifact: ResearchArtifact,
+    pub expired_at: String,
+    pub overdue_s: i64,
+}
+
+/// The in-memory index, built on first use and refreshed by
+/// [`spawn_refresher`].
+static RESEARCH_CACHE: OnceCell<Mutex<Vec<ResearchArtifact>>> = OnceCell::const_new();
+
+async fn research_cache() -> &'static Mutex<Vec<ResearchArtifact>> {
+    RESEARCH_CACHE
+        .get_or_init(|| async {
+            let built = rebuild(Vec::new()).await.unwrap_or_else(|e| {
+                tracing::warn!("Research index build failed: {}", e);
+                Vec::new()
+            });
+            Mutex::new(built)
+        })
+        .await
+}
+
+static SEARCH_CACHE: OnceCell<Mutex<Option<Arc<SearchIndex>>>> = OnceCell::const_new();
+
+async fn search_cache() -> &'static Mutex<Option<Arc<SearchIndex>>> {
+    SEARCH_CACHE
+        .get_or_init(|| async { Mutex::new(None) })
+        .await
+}
+
+/// The research index: `research/index.jsonl` if present; else the
+/// in-memory index of the current workspace.
+pub async fn artifacts() -> Vec<ResearchArtifact> {
+    let disk = Path::new(DISK_INDEX);
+    if disk.exists() {
+        return research::read_index(disk).unwrap_or_default();
+    }
+    research_cache().await.lock().await.clone()
+}
+
+/// Index `.` (no network), reusing `previous` for unchanged files.
+async fn rebuild(previous: Vec<ResearchArtifact>) -> anyhow::Result<Vec<ResearchArtifact>> {
+    tokio::task::spawn_blocking(move || research::update_index(Path::new("."), &previous)).await?
+}
+
+/// Re-index the in-memory index in place, under the index write lock.
+async fn refresh_cache() -> anyhow::Result<research::IndexDiff> {
+    let cache = research_cache().await;
+    tokio::task::spawn_blocking(move || {
+        let _writer = research::write_lock();
+        let previous = cache.blocking_lock().clone();
+        let current = research::update_index(Path::new("."), &previous)?;
+        let diff = research::diff(&previous, &current);
+        if !diff.is_empty() {
+            *cache.blocking_lock() = current;
+        }
+        Ok(diff)
+    })
+    .await?
+}
+
+/// Refresh the in-memory index every `RESEARCH_REFRESH_SECS` (default 60)
+/// while there is no `research/index.jsonl`. Requests never wait on a walk
+/// after the first build.
+pub fn spawn_refresher() {
+    let secs = std::env::var("RESEARCH_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_REFRESH_SECS);
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(secs));
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            if Path::new(DISK_INDEX).exists() {
+                continue;
+            }
+            match refresh_cache().await {
+                Ok(diff) if !diff.is_empty() => tracing::info!(
+                    "Research index: {} added, {} changed, {} removed",
+                    diff.added.len(),
+                    diff.changed.len(),
+                    diff.removed.len()
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Research index rebuild failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Search index over [`artifacts`], rebuilt (reusing unchanged documents)
+/// when the artifact set moves.
+pub async fn search_index() -> anyhow::Result<Arc<SearchIndex>> {
+    let artifacts = artifacts().await;
+    let mut cached = search_cache().await.lock().await;
+    if let Some(index) = cached.as_ref().filter(|i| i.covers(&artifacts)) {
+        return Ok(index.clone());
+    }
+    let previous = cached.take();
+    let index = tokio::task::spawn_blocking(move || {
+        SearchIndex::build(&artifacts, Path::new("."), previous.as_deref())
+    })
+    .await?;
+    Ok(cached.insert(Arc::new(index)).clone())
+}
+
+/// Artifacts past their TTL, most overdue first.
+pub async fn stale(now: DateTime<Utc>) -> Vec<StaleArtifact> {
+    let mut out: Vec<StaleArtifact> = artifacts()
+        .await
+        .into_iter()
+        .filter_map(|a| {
+            let at = research::expires_at(&a).filter(|at| *at < now)?;
+            Some(StaleArtifact {
+                expired_at: at.to_rfc3339(),
+                overdue_s: (now - at).num_seconds(),
+                artifact: a,
+            })
+        })
+        .collect();
+    out.sort_by_key(|s| std::cmp::Reverse(s.overdue_s));
+    out
+}
+
+/// Re-hash stale artifacts and refresh `verified_at` on the unchanged ones,
+/// in `research/index.jsonl` if present, else in the in-memory index.
+pub async fn reverify() -> anyhow::Result<ReverifyReport> {
+    let disk = Path::new(DISK_INDEX);
+    let now = Utc::now();
+    if !disk.exists() {
+        let cache = research_cache().await;
+        return Ok(tokio::task::spawn_blocking(move || {
+            let _writer = research::write_lock();
+            research::reverify(Path::new("."), &mut cache.blocking_lock(), now)
+        })
+        .await?);
+    }
+    tokio::task::spawn_blocking(move || {
+        let _writer = research::write_lock();
+        let mut index = research::read_index(disk)?;
+        let report = research::reverify(Path::new("."), &mut index, now);
+        if !report.refreshed.is_empty() {
+            research::write_index(disk, &index)?;
+        }
+        Ok(report)
+    })
+    .await?
+}
+
+/// Periodically re-verify stale artifacts (`RESEARCH_REVERIFY_SECS`,
+/// default hourly).
+pub fn spawn_reverifier() {
+    let secs = std::env::var("RESEARCH_REVERIFY_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_REVERIFY_SECS);
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(secs));
+        loop {
+            tick.tick().await;
+            match reverify().await {
+                Ok(r) if r.refreshed.len() + r.changed.len() + r.missing.len() > 0 => {
+                    tracing::info!(
+                        "Research re-verify: {} refreshed, {} changed, {} missing",
+                        r.refreshed.len(),
+                        r.changed.len(),
+                        r.missing.len()
+                    )
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Research re-verify failed: {}", e),
+            }
+        }
+    });
+}
+
+/// With `RESEARCH_WATCH` set, keep `research/index.jsonl` current from the
+/// working directory so [`artifacts`] always serves the live index.
+pub fn spawn_watcher() {
+    if !std::env::var("RESEARCH_WATCH").is_ok_and(|v| matches!(v.as_str(), "1" | "true")) {
+        return;
+    }
+    tokio::task::spawn_blocking(|| {
+        let roots = [std::path::PathBuf::from(".")];
+        let watched = watch::watch(
+            &roots,
+            Path::new(DISK_INDEX),
+            watch::DEFAULT_DEBOUNCE,
+            None,
+            |artifacts, diff| {
+                tracing::info!(
+                    "Research watch: {} artifacts ({} added, {} changed, {} removed)",
+                    artifacts.len(),
+                    diff.added.len(),
+                    diff.changed.len(),
+                    diff.removed.len()
+                );
+                true
+            },
+        );
+        if let Err(e) = watched {
+            tracing::warn!("Research watch stopped: {}", e);
+        }
+    });
+}
+
+/// Assess the artifacts a goal cites in `inputs.research` (ids or paths).
+/// A missing field yields no verdicts and leaves the index untouched.
+pub async fn cite(refs: Option<&Value>) -> Vec<ContextVerdict> {
+    let refs: Vec<String> = match refs {
+        None | Some(Value::Null) => return Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+            .collect(),
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(other) => vec![other.to_string()],
+    };
+    let index = artifacts().await;
+    let now = Utc::now();
+    refs.iter()
+        .enumerate()
+        .map(|(i, r)| assess(i, r, &index, now))
+        .collect()
+}
+
+fn assess(
+    index: usize,
+    cited: &str,
+    artifacts: &[ResearchArtifact],
+    now: DateTime<Utc>,
+) -> ContextVerdict {
+    let path = cited.split_once('#').map_or(cited, |(p, _)| p);
+    let verdict =
+        |artifact: Option<&ResearchArtifact>, verdict, reason: Option<String>| ContextVerdict {
+            index,
+            id: Some(cited.to_string()),
+            src: Some(format!("research:{}", path)),
+            verdict,
+            age_s: artifact
+                .and_then(|a| DateTime::parse_from_rfc3339(&a.ts).ok())
+                .map(|ts| (now - ts.with_timezone(&Utc)).num_seconds()),
+            reason,
+        };
+    let Some(current) = artifacts.iter().find(|a| a.path == path) else {
+        return verdict(
+            None,
+            Verdict::Rejected,
+            Some("unknown research artifact".into()),
+        );
+    };
+    if cited.contains('#') && cited != current.id {
+        return verdict(
+            Some(current),
+            Verdict::Drifted,
+            Some(format!("content changed; now {}", current.id)),
+        );
+    }
+    match research::expires_at(current).filter(|at| *at < now) {
+        Some(at) => verdict(
+            Some(current),
+            Verdict::Expired,
+            Some(format!("ttl ran out at {}", at.to_rfc3339())),
+        ),
+        None => verdict(Some(current), Verdict::Fresh, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{context, kernel::ExtendedBits};
+
+    #[test]
+    fn citing_a_stale_artifact_sets_drift() {
+        let now = Utc::now();
+        let artifact = |path: &str, ttl: u64| ResearchArtifact {
+            id: format!("{}#abc", path),
+            path: path.into(),
+            ts: (now - chrono::Duration::hours(2)).to_rfc3339(),
+            ttl,
+            checksum: "abc".into(),
+            ..Default::default()
+        };
+        let index = [artifact("stale.md", 60), artifact("fresh.md", 0)];
+
+        let fresh = [assess(0, "fresh.md#abc", &index, now)];
+        assert_eq!(fresh[0].verdict, Verdict::Fresh);
+        let mut bits = ExtendedBits::init();
+        context::mark_bits(&fresh, &mut bits);
+        assert_eq!(bits.d, 0.0);
+
+        let stale = [assess(0, "stale.md", &index, now)];
+        assert_eq!(stale[0].verdict, Verdict::Expired);
+        context::mark_bits(&stale, &mut bits);
+        assert_eq!((bits.d, bits.i), (1.0, 0.0));
+
+        assert_eq!(
+            assess(0, "fresh.md#old", &index, now).verdict,
+            Verdict::Drifted
+        );
+        assert_eq!(
+            assess(0, "missing.md", &index, now).verdict,
+            Verdict::Rejected
+        );
+    }
+}
@@ -1,162 +1,307 @@
+pub mod assertion;
 pub mod bits;
+pub mod calibration;
+pub mod checkpoint;
+pub mod clarify;
+pub mod context;
 pub mod executor;
+pub mod expr;
 pub mod goals;
 pub mod golden;
+pub mod history;
 pub mod kernel;
+pub mod library;
 pub mod openai;
+pub mod policies;
 pub mod policy;
+pub mod progress;
+pub mod sandbox;
+pub mod shadow;
+pub mod stats;
+pub mod suites;
+pub mod tape;
+pub mod trace;
 pub mod types;
 pub mod validate;
 pub mod verify;
 
-use bits::Bits;
-use chrono::Utc;
+use checkpoint::{PauseReason, Plan, RunState, RunStatus};
 use kernel::{ExtendedBits, KernelLoop, Meta2Proposal};
+use progress::{GateDecision, Phase};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, OnceCell};
-use types::{Manifest, Policy};
+use types::{Manifest, Policy, RunCtx, StreamEvent};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 static KERNEL: OnceCell<Mutex<KernelLoop>> = OnceCell::const_new();
 static KPI_HISTORY: OnceCell<Mutex<Vec<f32>>> = OnceCell::const_new();
 static TRACE_HISTORY: OnceCell<Mutex<Vec<ExtendedBits>>> = OnceCell::const_new();
 
-async fn kernel_loop() -> &'static Mutex<KernelLoop> {
+/// The run's kernel: its sandbox's when it has one, else the live one.
+async fn kernel_loop(ctx: &RunCtx) -> &Mutex<KernelLoop> {
+    match &ctx.sandbox {
+        Some(sandbox) => &sandbox.kernel,
+        None => live_kernel().await,
+    }
+}
+
+async fn live_kernel() -> &'static Mutex<KernelLoop> {
     KERNEL
         .get_or_init(|| async { Mutex::new(KernelLoop::new()) })
         .await
 }
 
-async fn kpi_history() -> &'static Mutex<Vec<f32>> {
-    KPI_HISTORY
-        .get_or_init(|| async { Mutex::new(Vec::new()) })
-        .await
+/// A copy of the live kernel, e.g. to start a [`sandbox::Sandbox`] from.
+pub async fn kernel_snapshot() -> KernelLoop {
+    live_kernel().await.lock().await.clone()
 }
 
-async fn trace_history() -> &'static Mutex<Vec<ExtendedBits>> {
-    TRACE_HISTORY
-        .get_or_init(|| async { Mutex::new(Vec::new()) })
-        .await
+async fn kpi_history(ctx: &RunCtx) -> &Mutex<Vec<f32>> {
+    match &ctx.sandbox {
+        Some(sandbox) => &sandbox.kpi_history,
+        None => {
+            KPI_HISTORY
+                .get_or_init(|| async { Mutex::new(Vec::new()) })
+                .await
+        }
+    }
+}
+
+async fn trace_history(ctx: &RunCtx) -> &Mutex<Vec<ExtendedBits>> {
+    match &ctx.sandbox {
+        Some(sandbox) => &sandbox.trace_history,
+        None => {
+            TRACE_HISTORY
+                .get_or_init(|| async { Mutex::new(Vec::new()) })
+                .await
+        }
+    }
+}
+
+/// Fresh run identifier, shared by the manifest and any progress events.
+pub fn new_run_id() -> String {
+    format!("r-{}", Uuid::new_v4())
 }
 
 pub async fn run(
+    ctx: &RunCtx,
     goal_id: &str,
     inputs: serde_json::Value,
     policy: &Policy,
 ) -> anyhow::Result<(Manifest, ExtendedBits, Option<Meta2Proposal>)> {
-    let kernel = kernel_loop().await;
-    let mut bits = ExtendedBits::init();
-    // Freshness filter: set Δ when any context item is expired
-    if let Some(ctx_items) = inputs.get("context").and_then(|v| v.as_array()) {
-        for item in ctx_items {
-            if let (Some(ts), Some(ttl)) = (
-                item.get("ts").and_then(|v| v.as_str()),
-                item.get("ttl").and_then(|v| v.as_i64()),
-            ) {
-                if let Ok(parsed) =
-                    chrono::DateTime::parse_from_rfc3339(ts).map(|dt| dt.with_timezone(&Utc))
-                {
-                    let age = (Utc::now() - parsed).num_seconds();
-                    if age > ttl {
-                        bits.d = 1.0;
-                    }
-                }
+    let state = RunState::new(ctx, goal_id, inputs, policy);
+    drive(ctx, state).await
+}
+
+/// What a client supplies to continue a paused run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ResumeInput {
+    /// Capabilities consented to (e.g. `network`), for approval pauses.
+    #[serde(default)]
+    pub approve: Vec<String>,
+    /// Fields merged over the run's original inputs.
+    #[serde(default)]
+    pub inputs: Option<serde_json::Value>,
+}
+
+/// Continue a paused or interrupted run from its checkpoint. Completed
+/// side-effecting phases are never repeated; new inputs only restart the run
+/// from `fetch_inputs` while `act_apply` has not happened yet.
+pub async fn resume(
+    ctx: &RunCtx,
+    input: ResumeInput,
+) -> anyhow::Result<(Manifest, ExtendedBits, Option<Meta2Proposal>)> {
+    let mut state = load(ctx).await?;
+    // `Running` here means the process died mid-run; pick up at the boundary.
+    if !matches!(state.status, RunStatus::Paused | RunStatus::Running) {
+        anyhow::bail!("run {} is not resumable ({:?})", state.run_id, state.status);
+    }
+    state.pause = None;
+    state.status = RunStatus::Running;
+    for cap in input.approve {
+        if !state.approvals.contains(&cap) {
+            state.approvals.push(cap);
+        }
+    }
+    if let Some(patch) = input.inputs {
+        merge_inputs(&mut state.inputs, patch);
+        if !state.completed.contains(&Phase::ActApply) {
+            state.completed.clear();
+        }
+    }
+    drive(ctx, state).await
+}
+
+fn merge_inputs(inputs: &mut serde_json::Value, patch: serde_json::Value) {
+    match (inputs.as_object_mut(), patch) {
+        (Some(base), serde_json::Value::Object(extra)) => base.extend(extra),
+        (_, patch) => *inputs = patch,
+    }
+}
+
+enum Step {
+    Continue,
+    Pause(PauseReason),
+}
+
+/// Run the remaining boundaries, checkpointing after each one.
+async fn drive(
+    ctx: &RunCtx,
+    mut state: RunState,
+) -> anyhow::Result<(Manifest, ExtendedBits, Option<Meta2Proposal>)> {
+    save(ctx, &mut state).await?;
+    while let Some(phase) = state.next_boundary() {
+        let mut gates = Vec::new();
+        let started = std::time::Instant::now();
+        let step = match phase {
+            Phase::FetchInputs => fetch_inputs(&mut state).await,
+            Phase::PlanActions => plan_actions(ctx, &mut state, &mut gates).await,
+            Phase::VerifyDryRun => verify_dry_run(ctx, &mut state, &mut gates).await,
+            Phase::ActApply => act_apply(ctx, &mut state, &mut gates).await,
+            _ => verify_post(ctx, &mut state, &mut gates).await,
+        };
+        let records = trace::records(
+            &state,
+            phase,
+            &gates,
+            started.elapsed().as_secs_f64() * 1000.0,
+        );
+        if ctx.sandbox.is_none() {
+            if let Err(e) = trace::append(&state.run_id, &records).await {
+                tracing::warn!("Failed to write trace for run {}: {}", state.run_id, e);
+            }
+        }
+        progress::emit_bits(ctx, phase, &state.bits, gates).await;
+        match step {
+            Ok(Step::Continue) => {
+                state.completed.push(phase);
+                save(ctx, &mut state).await?;
+            }
+            Ok(Step::Pause(reason)) => {
+                tracing::warn!("Run {} paused at {:?}: {:?}", state.run_id, phase, reason);
+                state.status = RunStatus::Paused;
+                state.pause = Some(reason);
+                save(ctx, &mut state).await?;
+                shadow::observe(ctx, &state).await;
+                progress::emit_bits(ctx, Phase::Paused, &state.bits, vec![]).await;
+                return Ok((paused_manifest(&state), state.bits, None));
+            }
+            Err(e) => {
+                state.status = RunStatus::Failed;
+                state.error = Some(e.to_string());
+                save(ctx, &mut state).await?;
+                return Err(e);
             }
         }
     }
+    state.status = RunStatus::Completed;
+    save(ctx, &mut state).await?;
+    shadow::observe(ctx, &state).await;
+    let manifest = completed_manifest(ctx, &state).await;
+    Ok((manifest, state.bits, state.meta2_proposal))
+}
+
+/// Checkpoint `state` in the run's sandbox, else in the run store.
+async fn save(ctx: &RunCtx, state: &mut RunState) -> anyhow::Result<()> {
+    match &ctx.sandbox {
+        Some(sandbox) => {
+            state.updated_at = chrono::Utc::now().to_rfc3339();
+            let mut runs = sandbox.runs.lock().await;
+            runs.insert(state.run_id.clone(), state.clone());
+            Ok(())
+        }
+        None => checkpoint::save(state).await,
+    }
+}
+
+async fn load(ctx: &RunCtx) -> anyhow::Result<RunState> {
+    match &ctx.sandbox {
+        Some(sandbox) => sandbox
+            .runs
+            .lock()
+            .await
+            .get(&ctx.run_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no checkpoint for run {}", ctx.run_id)),
+        None => checkpoint::load(&ctx.run_id).await,
+    }
+}
+
+async fn fetch_inputs(state: &mut RunState) -> anyhow::Result<Step> {
+    let mut bits = ExtendedBits::init();
+    // Context items: Δ when any is expired or has drifted from its hash,
+    // I when any had to be rejected (e.g. missing provenance)
+    state.context = context::assess(state.inputs.get("context"));
+    // Cited research artifacts count the same way, by their TTL and checksum
+    let cited = library::cite(state.inputs.get("research")).await;
+    state.context.extend(cited);
+    context::mark_bits(&state.context, &mut bits);
 
     // Set uncertainty based on goal difficulty
-    bits.u = match goal_id {
+    bits.u = match state.goal_id.as_str() {
         id if id.contains("easy") => 0.1,
         id if id.contains("hard") => 0.7,
         id if id.contains("impossible") => 0.9,
         _ => 0.3,
     };
+    state.bits = bits;
+    Ok(Step::Continue)
+}
 
-    // Ask-Act gate (inherent)
-    {
-        let kernel_guard = kernel.lock().await;
-        if !kernel_guard.ask_act_gate(&bits) {
-            return Err(anyhow::anyhow!(
-                "Ask-Act gate failed: A={}, P={}, Δ={}",
-                bits.a,
-                bits.p,
-                bits.d
-            ));
-        }
-    }
-
-    // Evidence gate (inherent)
-    let (needs_verification, confidence_tau) = {
-        let kernel_guard = kernel.lock().await;
-        (
-            !kernel_guard.evidence_gate(&bits),
-            kernel_guard.l2_params.confidence_gate_tau,
-        )
-    };
-    if needs_verification {
-        tracing::info!(
-            "Evidence gate triggered: U={:.2} >= τ={:.2}",
-            bits.u,
-            confidence_tau
+async fn plan_actions(
+    ctx: &RunCtx,
+    state: &mut RunState,
+    gates: &mut Vec<GateDecision>,
+) -> anyhow::Result<Step> {
+    let bits = &state.bits.clone();
+    // Ask-Act gate (inherent): pause for clarification rather than act blind
+    let policies = policies::current().await;
+    let act_if = &policies.ask_act.act_if;
+    let ask_act_open = kernel_loop(ctx)
+        .await
+        .lock()
+        .await
+        .ask_act_gate(act_if, bits);
+    let detail = format!("A={}, P={}, Δ={}", bits.a, bits.p, bits.d);
+    state.saw("ask_act", ask_act_open, bits);
+    gates.push(GateDecision::new(
+        "ask_act",
+        ask_act_open,
+        Some(format!("{} with {}", act_if, detail)),
+    ));
+    if !ask_act_open {
+        let request = clarify::request(
+            &state.inputs,
+            bits,
+            &detail,
+            &policies.ask_act.clarify_template,
         );
-        // In real system: run dry-run first
+        return Ok(Step::Pause(PauseReason::Clarification {
+            detail,
+            request: Some(request),
+        }));
     }
 
+    let goal_id = state.goal_id.as_str();
+    let message = state
+        .inputs
+        .get("message")
+        .and_then(|v| v.as_str())
+        .unwrap_or(if goal_id.contains("meta.omni") {
+            ""
+        } else {
+            "hello from one-engine"
+        })
+        .to_string();
+
     // Handle meta.omni through LM persona
     if goal_id.contains("meta.omni") {
-        let user_message = inputs.get("message").and_then(|v| v.as_str()).unwrap_or("");
-        let lm_result = goals::meta_omni::handle(user_message).await?;
-
-        // Extract reply from LM response
-        let _reply = lm_result
-            .get("reply")
-            .and_then(|v| v.as_str())
-            .unwrap_or("⟂ no reply");
-        let lm_bits = lm_result
-            .get("bits")
-            .cloned()
-            .unwrap_or_else(|| serde_json::json!({}));
-
-        // Update bits from LM response
-        if let Some(a) = lm_bits.get("A").and_then(|v| v.as_f64()) {
-            bits.a = a as f32;
-        }
-        if let Some(u) = lm_bits.get("U").and_then(|v| v.as_f64()) {
-            bits.u = u as f32;
-        }
-        if let Some(p) = lm_bits.get("P").and_then(|v| v.as_f64()) {
-            bits.p = p as f32;
-        }
-        if let Some(e) = lm_bits.get("E").and_then(|v| v.as_f64()) {
-            bits.e = e as f32;
-        }
-
-        let manifest = Manifest {
-            run_id: format!("r-{}", uuid::Uuid::new_v4()),
-            goal_id: goal_id.to_string(),
-            deliverables: vec![],
-            evidence: lm_result
-                .get("manifest")
-                .and_then(|m| m.get("evidence"))
-                .cloned()
-                .unwrap_or(lm_result.clone()),
-            bits: bits.clone().into(),
-        };
-
-        return Ok((manifest, bits, None));
+        state.plan = Some(Plan::Chat { message });
+        return Ok(Step::Continue);
     }
 
-    let message = if goal_id.contains("meta.omni") {
-        // This branch won't be reached due to early return above
-        "".to_string()
-    } else {
-        inputs
-            .get("message")
-            .and_then(|v| v.as_str())
-            .unwrap_or("hello from one-engine")
-            .to_string()
-    };
-
     // Simulate different outcomes based on goal type
     let (action, expected_success) = match goal_id {
         id if id.contains("impossible") => (executor::Action::Cli("false".to_string()), false),
@@ -175,17 +320,184 @@ pub async fn run(
             true,
         ),
     };
+    state.plan = Some(Plan::Cli {
+        action,
+        expected_success,
+    });
+    Ok(Step::Continue)
+}
 
-    let res = executor::execute(action, policy).await?;
+async fn verify_dry_run(
+    ctx: &RunCtx,
+    state: &mut RunState,
+    gates: &mut Vec<GateDecision>,
+) -> anyhow::Result<Step> {
+    let bits = &state.bits.clone();
+    // Evidence gate (inherent)
+    let (needs_verification, confidence_tau, evidence) = {
+        let kernel_guard = kernel_loop(ctx).await.lock().await;
+        (
+            !kernel_guard.evidence_gate_before_act(bits),
+            kernel_guard.l2_params.confidence_gate_tau,
+            kernel_guard.gates.evidence.to_string(),
+        )
+    };
+    let detail = format!(
+        "{} with U={:.2}, E=1 (not acted yet), τ={:.2}",
+        evidence, bits.u, confidence_tau
+    );
+    if needs_verification {
+        tracing::info!("Evidence gate triggered: {}", detail);
+        // In real system: run dry-run first
+    }
+    state.saw("evidence", !needs_verification, bits);
+    gates.push(GateDecision::new(
+        "evidence",
+        !needs_verification,
+        Some(detail),
+    ));
+    Ok(Step::Continue)
+}
+
+async fn act_apply(
+    ctx: &RunCtx,
+    state: &mut RunState,
+    gates: &mut Vec<GateDecision>,
+) -> anyhow::Result<Step> {
+    match state.plan.clone() {
+        Some(Plan::Chat { message }) => chat_apply(ctx, state, gates, &message).await,
+        Some(Plan::Cli {
+            action,
+            expected_success: _,
+        }) => {
+            // Capability gate: STRICT_CAPS=1 blocks outright; otherwise caps
+            // that CAPS.yaml marks require_approval need the client's consent.
+            if let Some(cap) = executor::capability_for(&action) {
+                if std::env::var("STRICT_CAPS").ok().as_deref() == Some("1") {
+                    gates.push(GateDecision::new("caps", false, Some(cap.to_string())));
+                    anyhow::bail!("capability gate blocked: {}", cap);
+                }
+                if policies::current().await.caps.require_approval(cap)
+                    && !state.approvals.iter().any(|c| c == cap)
+                {
+                    let executor::Action::Cli(cmd) = &action;
+                    match ctx.request_approval(cap, cmd).await {
+                        Some(true) => state.approvals.push(cap.to_string()),
+                        Some(false) => {
+                            gates.push(GateDecision::new("caps", false, Some(cap.to_string())));
+                            anyhow::bail!("capability denied by client: {}", cap);
+                        }
+                        None => {
+                            gates.push(GateDecision::new("caps", false, Some(cap.to_string())));
+                            return Ok(Step::Pause(PauseReason::Approval {
+                                capability: cap.to_string(),
+                                action: cmd.clone(),
+                            }));
+                        }
+                    }
+                }
+                gates.push(GateDecision::new("caps", true, Some(cap.to_string())));
+            }
+
+            let res = tape::execute(ctx, action, &state.policy).await?;
+            let bits = &mut state.bits;
+            if res.drift {
+                bits.d = 1.0;
+            }
+            if !res.ok {
+                bits.e = 1.0;
+                // L2 micro-adaptation: increase uncertainty for future similar tasks
+                bits.u = (bits.u + 0.2).min(1.0);
+            }
+            state.exec = Some(res);
+            Ok(Step::Continue)
+        }
+        None => anyhow::bail!("run {} reached act_apply without a plan", state.run_id),
+    }
+}
 
-    if res.drift {
-        bits.d = 1.0;
+async fn chat_apply(
+    ctx: &RunCtx,
+    state: &mut RunState,
+    gates: &mut Vec<GateDecision>,
+    user_message: &str,
+) -> anyhow::Result<Step> {
+    let lm_result = tape::lm(ctx, user_message, async {
+        // A recording sandbox (golden record) captures the real LM.
+        if let (Some(sandbox), None) = (&ctx.sandbox, &ctx.tape) {
+            Ok(goals::meta_omni::mock(user_message, sandbox.seed))
+        } else if ctx.stream.is_some() {
+            goals::meta_omni::handle_streaming(
+                user_message,
+                |text| ctx.send(StreamEvent::Token(text.to_string())),
+                |reason| ctx.send(StreamEvent::Reset(reason.to_string())),
+            )
+            .await
+        } else {
+            goals::meta_omni::handle(user_message).await
+        }
+    })
+    .await?;
+
+    let bits = &mut state.bits;
+    let lm_bits = lm_result
+        .get("bits")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    // Update bits from LM response
+    if let Some(a) = lm_bits.get("A").and_then(|v| v.as_f64()) {
+        bits.a = a as f32;
+    }
+    if let Some(u) = lm_bits.get("U").and_then(|v| v.as_f64()) {
+        bits.u = u as f32;
     }
-    if !res.ok {
-        bits.e = 1.0;
-        // L2 micro-adaptation: increase uncertainty for future similar tasks
-        bits.u = (bits.u + 0.2).min(1.0);
+    if let Some(p) = lm_bits.get("P").and_then(|v| v.as_f64()) {
+        bits.p = p as f32;
+    }
+    if let Some(e) = lm_bits.get("E").and_then(|v| v.as_f64()) {
+        bits.e = e as f32;
+    }
+    ctx.send(StreamEvent::ProvisionalBits(bits.clone().into()));
+
+    // A proposed patch is a file write; interactive clients approve it.
+    if let Some(files) = lm_result.pointer("/patch/files").and_then(|f| f.as_array()) {
+        let paths: Vec<String> = files
+            .iter()
+            .filter_map(|f| f.get("path").and_then(|p| p.as_str()))
+            .map(|p| p.to_string())
+            .collect();
+        let action = format!("apply patch to {}", paths.join(", "));
+        if let Some(approved) = ctx.request_approval("file_write", &action).await {
+            gates.push(GateDecision::new("caps", approved, Some(action)));
+            if approved {
+                state.deliverables.extend(paths);
+            } else {
+                state.bits.p = 0.0;
+            }
+        }
     }
+    state.lm_result = Some(lm_result);
+    Ok(Step::Continue)
+}
+
+async fn verify_post(
+    ctx: &RunCtx,
+    state: &mut RunState,
+    gates: &mut Vec<GateDecision>,
+) -> anyhow::Result<Step> {
+    let (res, expected_success) = match (&state.exec, &state.plan) {
+        (
+            Some(res),
+            Some(Plan::Cli {
+                expected_success, ..
+            }),
+        ) => (res.clone(), *expected_success),
+        // Chat turns carry their own bits from the LM; nothing to verify.
+        _ => return Ok(Step::Continue),
+    };
+    let kernel = kernel_loop(ctx).await;
+    let bits = &mut state.bits;
 
     let passed = verify::check_minimal(&res);
     let legacy_bits: types::Bits = bits.clone().into();
@@ -199,7 +511,7 @@ pub async fn run(
     // L3 meta² check: should we propose policy changes?
     let current_evidence_coverage = bits.t; // Simplified: use trust as proxy
     {
-        let history = kpi_history().await;
+        let history = kpi_history(ctx).await;
         let mut guard = history.lock().await;
         guard.push(current_evidence_coverage);
         if guard.len() > 200 {
@@ -208,11 +520,11 @@ pub async fn run(
     }
 
     let history_snapshot = {
-        let history = kpi_history().await;
+        let history = kpi_history(ctx).await;
         history.lock().await.clone()
     };
 
-    let meta2_proposal = {
+    state.meta2_proposal = {
         let mut kernel_guard = kernel.lock().await;
         if kernel_guard.should_wake_l3(&history_snapshot) {
             bits.m = 1.0; // Meta-change bit set
@@ -221,73 +533,132 @@ pub async fn run(
             None
         }
     };
+    // Live proposals are shadowed before anyone promotes them.
+    if let (Some(proposal), None, None) = (&state.meta2_proposal, &ctx.sandbox, &ctx.tape) {
+        shadow::start_for_proposal(proposal).await;
+    }
 
     // STRUCTURAL VALIDATION: Enforce kernel contract
-    {
-        let kernel_guard = kernel.lock().await;
-        if let Err(e) = kernel_guard.validate_bits_complete(&bits) {
-            return Err(anyhow::anyhow!("Kernel contract violation: {}", e));
-        }
+    let contract = kernel.lock().await.validate_bits_complete(bits);
+    gates.push(GateDecision::new(
+        "kernel_contract",
+        contract.is_ok(),
+        contract.as_ref().err().cloned(),
+    ));
+    if let Err(e) = contract {
+        return Err(anyhow::anyhow!("Kernel contract violation: {}", e));
     }
 
     // STRUCTURAL GATE: Ask-Act enforcement
+    let goal_id = state.goal_id.as_str();
     if goal_id.contains("action") || goal_id.contains("execute") {
-        let kernel_guard = kernel.lock().await;
-        if let Err(e) = kernel_guard.enforce_ask_act_gate(&bits) {
+        let enforced = kernel.lock().await.enforce_ask_act_gate(bits);
+        gates.push(GateDecision::new(
+            "ask_act_enforced",
+            enforced.is_ok(),
+            enforced.as_ref().err().cloned(),
+        ));
+        if let Err(e) = enforced {
             tracing::warn!("Ask-Act gate blocked action: {}", e);
             // Return clarification request instead of proceeding
-            let clarification = format!("Ask-Act gate: {}. Need P=1, A=1, Δ=0", e);
-            let blocked_manifest = Manifest {
-                run_id: uuid::Uuid::new_v4().to_string(),
-                goal_id: goal_id.to_string(),
-                deliverables: vec!["clarification_required".to_string()],
-                evidence: serde_json::json!({"stdout": clarification, "stderr": "", "files": []}),
-                bits: Bits {
-                    a: bits.a,
-                    u: bits.u,
-                    p: bits.p,
-                    e: bits.e,
-                    d: bits.d,
-                    i: bits.i,
-                    r: bits.r,
-                    t: bits.t,
-                    m: bits.m,
-                },
-            };
-            return Ok((blocked_manifest, bits, None));
+            let template = policies::current().await.ask_act.clarify_template.clone();
+            let request = clarify::request(&state.inputs, bits, &e, &template);
+            return Ok(Step::Pause(PauseReason::Clarification {
+                detail: e,
+                request: Some(request),
+            }));
         }
     }
 
     // Store trace for self-observation
     {
-        let trace = trace_history().await;
+        let trace = trace_history(ctx).await;
         let mut guard = trace.lock().await;
         guard.push(bits.clone());
         if guard.len() > 100 {
             guard.remove(0);
         }
     }
+    Ok(Step::Continue)
+}
 
-    let l2_params_snapshot = {
-        let kernel_guard = kernel.lock().await;
-        kernel_guard.l2_params.clone()
+fn paused_manifest(state: &RunState) -> Manifest {
+    let (deliverable, stdout, resume) = match &state.pause {
+        Some(reason @ PauseReason::Clarification { detail, request }) => (
+            reason.deliverable(),
+            match request {
+                Some(request) => request.rendered.clone(),
+                None => format!("Ask-Act gate: {}. Need P=1, A=1, Δ=0", detail),
+            },
+            format!("POST /runs/{}/clarify", state.run_id),
+        ),
+        Some(reason @ PauseReason::Approval { capability, action }) => (
+            reason.deliverable(),
+            format!("Capability '{}' requires approval: {}", capability, action),
+            format!("POST /runs/{}/resume", state.run_id),
+        ),
+        None => ("paused", String::new(), String::new()),
     };
+    let mut evidence = serde_json::json!({
+        "stdout": stdout,
+        "stderr": "",
+        "files": [],
+        "pause": state.pause,
+        "resume": resume,
+    });
+    with_context_verdicts(state, &mut evidence);
+    Manifest {
+        run_id: state.run_id.clone(),
+        goal_id: state.goal_id.clone(),
+        deliverables: vec![deliverable.to_string()],
+        evidence,
+        bits: state.bits.clone().into(),
+    }
+}
 
-    let manifest = Manifest {
-        run_id: format!("r-{}", Uuid::new_v4()),
-        goal_id: goal_id.to_string(),
-        deliverables: vec![],
-        evidence: serde_json::json!({
-            "stdout": res.stdout,
-            "expected_success": expected_success,
-            "actual_success": passed,
-            "l2_params": l2_params_snapshot,
-            "meta2_triggered": bits.m > 0.0
-        }),
-        bits: bits.clone().into(), // Convert to legacy Bits for compatibility
-    };
+/// Record per-item context verdicts alongside the evidence they qualify.
+fn with_context_verdicts(state: &RunState, evidence: &mut serde_json::Value) {
+    if state.context.is_empty() {
+        return;
+    }
+    if let Some(obj) = evidence.as_object_mut() {
+        obj.insert("context".to_string(), serde_json::json!(state.context));
+    }
+}
 
-    Ok((manifest, bits, meta2_proposal))
+async fn completed_manifest(ctx: &RunCtx, state: &RunState) -> Manifest {
+    let mut evidence = match (&state.plan, &state.exec, &state.lm_result) {
+        (Some(Plan::Chat { .. }), _, Some(lm_result)) => lm_result
+            .get("manifest")
+            .and_then(|m| m.get("evidence"))
+            .cloned()
+            .unwrap_or(lm_result.clone()),
+        (
+            Some(Plan::Cli {
+                expected_success, ..
+            }),
+            Some(res),
+            _,
+        ) => {
+            let l2_params_snapshot = kernel_loop(ctx).await.lock().await.l2_params.clone();
+            serde_json::json!({
+                "stdout": res.stdout,
+                "expected_success": expected_success,
+                "actual_success": verify::check_minimal(res),
+                "l2_params": l2_params_snapshot,
+                "meta2_triggered": state.bits.m > 0.0
+            })
+        }
+        _ => serde_json::json!({}),
+    };
+    with_context_verdicts(state, &mut evidence);
+    Manifest {
+        run_id: state.run_id.clone(),
+        goal_id: state.goal_id.clone(),
+        deliverables: state.deliverables.clone(),
+        evidence,
+        bits: state.bits.clone().into(), // Convert to legacy Bits for compatibility
+    }
 }
 
 // Convert ExtendedBits to legacy Bits for API compatibility
@@ -306,3 +677,173 @@ impl From<ExtendedBits> for types::Bits {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn policy() -> Policy {
+        Policy {
+            gamma_gate: 0.5,
+            time_ms: 5000,
+            max_risk: 0.5,
+            tiny_diff_loc: 50,
+        }
+    }
+
+    fn sandboxed(tenant: &str) -> RunCtx {
+        let mut ctx = RunCtx::new(Some(tenant.to_string()));
+        ctx.sandbox = Some(Arc::new(sandbox::Sandbox::new(KernelLoop::new(), 0)));
+        ctx
+    }
+
+    /// Every event of `run_id` until (and including) `last`.
+    async fn events_until(
+        rx: &mut tokio::sync::mpsc::Receiver<progress::ProgressEvent>,
+        last: Phase,
+    ) -> Vec<progress::ProgressEvent> {
+        let mut events = Vec::new();
+        while events
+            .last()
+            .is_none_or(|e: &progress::ProgressEvent| e.phase != last)
+        {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("progress event in time")
+                .expect("progress stream open");
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn a_paused_run_resumes_from_its_checkpoint_and_applies_once() {
+        let ctx = sandboxed("resume-test");
+        let mut rx = progress::subscribe(ctx.tenant.clone(), Some(ctx.run_id.clone()), None).await;
+        // An expired context item sets Δ, so the ask-act gate pauses the run.
+        let inputs = serde_json::json!({
+            "message": "hi",
+            "context": [{"id": "a", "src": "inline", "ts": "2020-01-01T00:00:00Z", "ttl": 10,
+                         "hash": format!("sha256:{}", "0".repeat(64))}],
+        });
+        let (manifest, _, _) = run(&ctx, "easy.test", inputs, &policy()).await.unwrap();
+        assert_eq!(manifest.deliverables, ["clarification_required"]);
+        let paused = load(&ctx).await.unwrap();
+        assert_eq!(paused.status, RunStatus::Paused);
+        assert_eq!(paused.completed, [Phase::FetchInputs]);
+        let first = events_until(&mut rx, Phase::Paused).await;
+
+        let input = ResumeInput {
+            approve: Vec::new(),
+            inputs: Some(serde_json::json!({"context": []})),
+        };
+        let (manifest, bits, _) = resume(&ctx, input).await.unwrap();
+        assert!(!checkpoint::is_paused(&manifest));
+        assert_eq!(bits.d, 0.0);
+        assert_eq!(load(&ctx).await.unwrap().status, RunStatus::Completed);
+        let second = events_until(&mut rx, Phase::VerifyPost).await;
+        let applied = first
+            .iter()
+            .chain(&second)
+            .filter(|e| e.phase == Phase::ActApply)
+            .count();
+        assert_eq!(applied, 1);
+        assert!(resume(&ctx, ResumeInput::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn clarifying_a_run_that_cites_a_stale_artifact_completes_it() {
+        let ctx = sandboxed("clarify-test");
+        // A checksum that no longer matches marks the citation as drifted.
+        let inputs = serde_json::json!({"message": "hi", "research": ["README.md#old"]});
+        let (manifest, _, _) = run(&ctx, "easy.test", inputs, &policy()).await.unwrap();
+        assert_eq!(manifest.deliverables, ["clarification_required"]);
+        let paused = load(&ctx).await.unwrap();
+        let Some(checkpoint::PauseReason::Clarification {
+            request: Some(request),
+            ..
+        }) = &paused.pause
+        else {
+            panic!("expected a clarification pause, got {:?}", paused.pause);
+        };
+        assert_eq!(request.ask, clarify::TriadSlot::Evidence);
+
+        let patch = clarify::answer_patch(&paused.inputs, request.ask, "log://build/42").await;
+        assert_eq!(patch["research"], serde_json::json!([]));
+        let input = ResumeInput {
+            approve: Vec::new(),
+            inputs: Some(patch),
+        };
+        let (manifest, bits, _) = resume(&ctx, input).await.unwrap();
+        assert!(!checkpoint::is_paused(&manifest));
+        assert_eq!(bits.d, 0.0);
+        assert_eq!(load(&ctx).await.unwrap().status, RunStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn resuming_after_act_apply_never_applies_again() {
+        let ctx = sandboxed("resume-test");
+        let mut state = RunState::new(&ctx, "easy.test", serde_json::json!({}), &policy());
+        state.completed = checkpoint::BOUNDARIES[..4].to_vec();
+        state.status = RunStatus::Paused;
+        state.plan = Some(Plan::Cli {
+            action: executor::Action::Cli("echo again".to_string()),
+            expected_success: true,
+        });
+        state.exec = Some(executor::ExecResult {
+            ok: true,
+            drift: false,
+            stdout: "applied once".to_string(),
+        });
+        save(&ctx, &mut state).await.unwrap();
+        let mut rx = progress::subscribe(ctx.tenant.clone(), Some(ctx.run_id.clone()), None).await;
+
+        // New inputs after act_apply do not restart the run either.
+        let input = ResumeInput {
+            approve: Vec::new(),
+            inputs: Some(serde_json::json!({"message": "changed"})),
+        };
+        let (manifest, _, _) = resume(&ctx, input).await.unwrap();
+        assert_eq!(manifest.evidence["stdout"], "applied once");
+        let events = events_until(&mut rx, Phase::VerifyPost).await;
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn runs_emit_one_event_per_boundary_with_bits_and_gates() {
+        let ctx = sandboxed("progress-test");
+        let mut rx = progress::subscribe(ctx.tenant.clone(), Some(ctx.run_id.clone()), None).await;
+        let (manifest, _, _) = run(
+            &ctx,
+            "easy.test",
+            serde_json::json!({"message": "hi"}),
+            &policy(),
+        )
+        .await
+        .unwrap();
+        assert!(!checkpoint::is_paused(&manifest));
+
+        let events = events_until(&mut rx, Phase::VerifyPost).await;
+        let phases: Vec<Phase> = events.iter().map(|e| e.phase).collect();
+        assert_eq!(phases, checkpoint::BOUNDARIES);
+        assert!(events.iter().all(|e| e.bits.is_some()
+            && e.run_id == ctx.run_id
+            && e.tenant.as_deref() == Some("progress-test")));
+        let gates = |phase: Phase| -> Vec<String> {
+            events
+                .iter()
+                .find(|e| e.phase == phase)
+                .unwrap()
+                .gates
+                .iter()
+                .map(|g| g.gate.clone())
+                .collect()
+        };
+        assert_eq!(gates(Phase::PlanActions), ["ask_act"]);
+        assert_eq!(gates(Phase::VerifyDryRun), ["evidence"]);
+        assert!(gates(Phase::VerifyPost).contains(&"kernel_contract".to_string()));
+        assert_eq!(events[4].bits.as_ref().unwrap().e, 0.0);
+    }
+}
@@ -1,11 +1,12 @@
 use anyhow::Result;
-use reqwest::{Client, StatusCode};
+use reqwest::{Client, Response, StatusCode};
 use serde_json::{json, Value};
 
-pub async fn chat_json(system: &str, user: &str) -> Result<Value> {
+async fn send(system: &str, user: &str, stream: bool) -> Result<Response> {
     let body = json!({
       "model":"gpt-3.5-turbo",
       "response_format":{"type":"json_object"},
+      "stream": stream,
       "messages":[{"role":"system","content":system},{"role":"user","content":user}]
     });
     let client = Client::builder()
@@ -20,27 +21,78 @@ pub async fn chat_json(system: &str, user: &str) -> Result<Value> {
         .json(&body)
         .send()
         .await?;
+    Ok(res)
+}
+
+fn http_error(status: StatusCode, text: String) -> Value {
+    eprintln!("[openai] http_error status={} body={}", status, text);
+    json!({"reply":"⟂ openai http_error","error":text,"bits":{"A":0,"U":1,"P":0,"E":1,"Δ":0,"I":0,"R":0,"T":0,"M":0}})
+}
+
+// Expect JSON in content; fall back gracefully if plain text
+fn parse_content(content: &str) -> Value {
+    match serde_json::from_str::<Value>(content) {
+        Ok(j) => j,
+        Err(_) => {
+            eprintln!("[openai] non-json content='{}'", content);
+            json!({"reply":content,"bits":{"A":1,"U":0,"P":0,"E":0,"Δ":0,"I":0,"R":0,"T":1,"M":0}})
+        }
+    }
+}
+
+pub async fn chat_json(system: &str, user: &str) -> Result<Value> {
+    let res = send(system, user, false).await?;
     let status = res.status();
     let text = res.text().await.unwrap_or_default();
     if status != StatusCode::OK {
-        eprintln!("[openai] http_error status={} body={}", status, text);
-        return Ok(
-            json!({"reply":"⟂ openai http_error","error":text,"bits":{"A":0,"U":1,"P":0,"E":1,"Δ":0,"I":0,"R":0,"T":0,"M":0}}),
-        );
+        return Ok(http_error(status, text));
     }
-    // Expect JSON in content; fall back gracefully if plain text
     let v: Value = serde_json::from_str(&text).unwrap_or_else(|_| json!({}));
     let content = v
         .pointer("/choices/0/message/content")
         .and_then(|c| c.as_str())
         .unwrap_or("");
-    match serde_json::from_str::<Value>(content) {
-        Ok(j) => Ok(j),
-        Err(_) => {
-            eprintln!("[openai] non-json content='{}'", content);
-            Ok(
-                json!({"reply":content,"bits":{"A":1,"U":0,"P":0,"E":0,"Δ":0,"I":0,"R":0,"T":1,"M":0}}),
-            )
+    Ok(parse_content(content))
+}
+
+/// Same contract as [`chat_json`], but requests a streamed completion and
+/// hands each content delta to `on_delta` as it arrives.
+pub async fn chat_json_stream(
+    system: &str,
+    user: &str,
+    mut on_delta: impl FnMut(&str),
+) -> Result<Value> {
+    let mut res = send(system, user, true).await?;
+    let status = res.status();
+    if status != StatusCode::OK {
+        let text = res.text().await.unwrap_or_default();
+        return Ok(http_error(status, text));
+    }
+    let mut pending = String::new();
+    let mut content = String::new();
+    'read: while let Some(chunk) = res.chunk().await? {
+        pending.push_str(&String::from_utf8_lossy(&chunk));
+        // Server-sent events: one `data: {...}` per line; keep any partial tail.
+        while let Some(nl) = pending.find('\n') {
+            let line: String = pending.drain(..=nl).collect();
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                break 'read;
+            }
+            let Ok(v) = serde_json::from_str::<Value>(data) else {
+                continue;
+            };
+            if let Some(delta) = v
+                .pointer("/choices/0/delta/content")
+                .and_then(|c| c.as_str())
+            {
+                content.push_str(delta);
+                on_delta(delta);
+            }
         }
     }
+    Ok(parse_content(&content))
 }
@@ -0,0 +1,453 @@
+use super::checkpoint::BOUNDARIES;
+use super::expr::Expr;
+use super::kernel::Gates;
+use super::progress::Phase;
+use super::types::Policy;
+use anyhow::Context;
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell};
+
+const DEFAULT_DIR: &str = "policies";
+const DEFAULT_POLL_SECS: u64 = 2;
+const BIT_NAMES: [&str; 9] = ["A", "U", "P", "E", "Δ", "I", "R", "T", "M"];
+
+/// Files making up a policy set, relative to the policy directory. The
+/// bundled copies are the fallback when the directory cannot be loaded.
+const FILES: [(&str, &str); 7] = [
+    (
+        "BOUNDARIES.yaml",
+        include_str!("../../policies/BOUNDARIES.yaml"),
+    ),
+    ("CAPS.yaml", include_str!("../../policies/CAPS.yaml")),
+    ("LANES.yaml", include_str!("../../policies/LANES.yaml")),
+    (
+        "META2_RULES.yaml",
+        include_str!("../../policies/META2_RULES.yaml"),
+    ),
+    (
+        "POLICY.ask_act.yaml",
+        include_str!("../../policies/POLICY.ask_act.yaml"),
+    ),
+    ("RETRY.yaml", include_str!("../../policies/RETRY.yaml")),
+    ("default.json", include_str!("../../policies/default.json")),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Boundaries {
+    pub boundaries: Vec<Boundary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Boundary {
+    pub id: Phase,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Capability consent rules from `CAPS.yaml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Caps {
+    pub caps: HashMap<String, CapRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CapRule {
+    pub require_approval: bool,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl Caps {
+    pub fn require_approval(&self, cap: &str) -> bool {
+        self.caps.get(cap).is_some_and(|r| r.require_approval)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lanes {
+    pub lanes: Vec<Lane>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lane {
+    pub id: String,
+    pub owner: String,
+    pub sla_minutes: u64,
+    pub required_bits: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Meta2Rules {
+    pub targets: BTreeMap<String, Target>,
+    pub guards: Guards,
+    pub adapters: BTreeMap<String, Adapter>,
+}
+
+/// A KPI bound: `min`, `max` and/or an inclusive `range`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub range: Option<[f32; 2]>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Guards {
+    pub weekly_param_delta_max: ParamDeltaGuard,
+    pub shadow_rollout_pct: f32,
+    #[serde(default)]
+    pub revert_if: Vec<RevertRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamDeltaGuard {
+    pub theta: f32,
+    pub c: f32,
+    pub lambda: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevertRule {
+    pub kpi: String,
+    pub op: String,
+    pub value: f32,
+    pub window: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Adapter {
+    pub learner: String,
+    #[serde(default)]
+    pub tune: BTreeMap<String, Vec<f32>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskAct {
+    pub gamma_min: f32,
+    pub max_diff_size: u32,
+    pub act_if: Expr,
+    pub clarify_template: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Retry {
+    pub backoff: Backoff,
+    pub strategies: Strategies,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Backoff {
+    pub base_ms: u64,
+    pub factor: f32,
+    pub max_ms: u64,
+    pub max_retries: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Strategies {
+    pub order: Vec<String>,
+    #[serde(flatten)]
+    pub strategies: BTreeMap<String, Strategy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Strategy {
+    pub enabled: bool,
+}
+
+/// Every file under `policies/`, parsed and validated.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicySet {
+    /// Bumped each time a changed set is loaded.
+    pub version: u64,
+    /// SHA-256 over the file names and contents.
+    pub hash: String,
+    pub loaded_at: String,
+    pub source: String,
+    pub boundaries: Boundaries,
+    pub caps: Caps,
+    pub lanes: Lanes,
+    pub meta2_rules: Meta2Rules,
+    pub ask_act: AskAct,
+    pub retry: Retry,
+    pub default_policy: Policy,
+}
+
+impl PolicySet {
+    /// Load and validate every policy file in `dir`.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let mut sources = Vec::new();
+        for (name, _) in FILES {
+            let path = dir.join(name);
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("cannot read {}", path.display()))?;
+            sources.push((name, text));
+        }
+        Self::parse(&sources, &dir.display().to_string())
+    }
+
+    /// The copies compiled into the binary.
+    pub fn bundled() -> Self {
+        let sources: Vec<(&str, String)> = FILES
+            .iter()
+            .map(|(name, text)| (*name, text.to_string()))
+            .collect();
+        Self::parse(&sources, "bundled").expect("bundled policies are valid")
+    }
+
+    fn parse(sources: &[(&str, String)], source: &str) -> anyhow::Result<Self> {
+        let text = |name: &str| {
+            sources
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, t)| t.as_str())
+                .unwrap_or_default()
+        };
+        let set = Self {
+            version: 1,
+            hash: hash(sources),
+            loaded_at: Utc::now().to_rfc3339(),
+            source: source.to_string(),
+            boundaries: parse_file("BOUNDARIES.yaml", text("BOUNDARIES.yaml"))?,
+            caps: parse_file("CAPS.yaml", text("CAPS.yaml"))?,
+            lanes: parse_file("LANES.yaml", text("LANES.yaml"))?,
+            meta2_rules: parse_file("META2_RULES.yaml", text("META2_RULES.yaml"))?,
+            ask_act: parse_file("POLICY.ask_act.yaml", text("POLICY.ask_act.yaml"))?,
+            retry: parse_file("RETRY.yaml", text("RETRY.yaml"))?,
+            default_policy: parse_file("default.json", text("default.json"))?,
+        };
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Checks beyond what the types enforce; errors name the file and field.
+    fn validate(&self) -> anyhow::Result<()> {
+        let ids: Vec<Phase> = self.boundaries.boundaries.iter().map(|b| b.id).collect();
+        if ids != BOUNDARIES {
+            anyhow::bail!(
+                "BOUNDARIES.yaml: boundaries must be {:?} in that order, got {:?}",
+                BOUNDARIES,
+                ids
+            );
+        }
+
+        let mut lane_ids = Vec::new();
+        for (i, lane) in self.lanes.lanes.iter().enumerate() {
+            if lane_ids.contains(&&lane.id) {
+                anyhow::bail!("LANES.yaml: lanes[{}].id `{}` is duplicated", i, lane.id);
+            }
+            lane_ids.push(&lane.id);
+            if lane.sla_minutes == 0 {
+                anyhow::bail!("LANES.yaml: lanes[{}].sla_minutes must be > 0", i);
+            }
+            if let Some(bit) = lane
+                .required_bits
+                .iter()
+                .find(|b| !BIT_NAMES.contains(&b.as_str()))
+            {
+                anyhow::bail!(
+                    "LANES.yaml: lanes[{}].required_bits has unknown bit `{}`",
+                    i,
+                    bit
+                );
+            }
+        }
+
+        for (name, target) in &self.meta2_rules.targets {
+            if let Some([lo, hi]) = target.range {
+                if lo > hi {
+                    anyhow::bail!("META2_RULES.yaml: targets.{}.range is empty", name);
+                }
+            }
+        }
+        unit(
+            "META2_RULES.yaml",
+            "guards.shadow_rollout_pct",
+            self.meta2_rules.guards.shadow_rollout_pct,
+        )?;
+
+        Gates::check_idents(&self.ask_act.act_if).context("POLICY.ask_act.yaml: act_if")?;
+        unit("POLICY.ask_act.yaml", "gamma_min", self.ask_act.gamma_min)?;
+
+        let backoff = &self.retry.backoff;
+        if backoff.factor < 1.0 || backoff.base_ms > backoff.max_ms {
+            anyhow::bail!("RETRY.yaml: backoff needs factor >= 1 and base_ms <= max_ms");
+        }
+        if let Some(name) = self
+            .retry
+            .strategies
+            .order
+            .iter()
+            .find(|s| !self.retry.strategies.strategies.contains_key(*s))
+        {
+            anyhow::bail!(
+                "RETRY.yaml: strategies.order names undefined strategy `{}`",
+                name
+            );
+        }
+
+        let policy = &self.default_policy;
+        unit("default.json", "gamma_gate", policy.gamma_gate)?;
+        unit("default.json", "max_risk", policy.max_risk)?;
+        if policy.time_ms == 0 {
+            anyhow::bail!("default.json: time_ms must be > 0");
+        }
+        Ok(())
+    }
+}
+
+fn parse_file<T: DeserializeOwned>(name: &str, text: &str) -> anyhow::Result<T> {
+    let parsed = if name.ends_with(".json") {
+        serde_json::from_str(text).map_err(anyhow::Error::from)
+    } else {
+        serde_yaml::from_str(text).map_err(anyhow::Error::from)
+    };
+    parsed.with_context(|| format!("invalid {}", name))
+}
+
+fn unit(file: &str, field: &str, value: f32) -> anyhow::Result<()> {
+    if !(0.0..=1.0).contains(&value) {
+        anyhow::bail!("{}: {} must be within [0, 1], got {}", file, field, value);
+    }
+    Ok(())
+}
+
+fn hash(sources: &[(&str, String)]) -> String {
+    let mut hasher = Sha256::new();
+    for (name, text) in sources {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(text.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn policy_dir() -> PathBuf {
+    PathBuf::from(std::env::var("POLICY_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string()))
+}
+
+static POLICIES: OnceCell<Mutex<Arc<PolicySet>>> = OnceCell::const_new();
+
+async fn store() -> &'static Mutex<Arc<PolicySet>> {
+    POLICIES
+        .get_or_init(|| async {
+            let set = PolicySet::load(&policy_dir()).unwrap_or_else(|e| {
+                tracing::error!("policy load failed: {:#}; using bundled policies", e);
+                PolicySet::bundled()
+            });
+            Mutex::new(Arc::new(set))
+        })
+        .await
+}
+
+/// Load the policy set at startup; an invalid set is a startup error. With
+/// no policy directory the bundled set is used.
+pub async fn init() -> anyhow::Result<Arc<PolicySet>> {
+    let dir = policy_dir();
+    let set = if dir.is_dir() {
+        PolicySet::load(&dir)?
+    } else {
+        tracing::warn!("{} not found; using bundled policies", dir.display());
+        PolicySet::bundled()
+    };
+    let set = Arc::new(set);
+    POLICIES
+        .set(Mutex::new(set.clone()))
+        .map_err(|_| anyhow::anyhow!("policies already initialised"))?;
+    Ok(set)
+}
+
+/// The active policy set.
+pub async fn current() -> Arc<PolicySet> {
+    store().await.lock().await.clone()
+}
+
+/// Poll the policy directory (every `POLICY_RELOAD_SECS`, default 2) while
+/// it exists, and swap in changed sets. An invalid edit is logged and the
+/// last good set stays active.
+pub fn spawn_reloader() {
+    let secs = std::env::var("POLICY_RELOAD_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_POLL_SECS);
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(secs));
+        let mut last_error: Option<String> = None;
+        loop {
+            tick.tick().await;
+            let dir = policy_dir();
+            if !dir.is_dir() {
+                continue;
+            }
+            let active = current().await;
+            match PolicySet::load(&dir) {
+                Ok(mut set) if set.hash != active.hash => {
+                    set.version = active.version + 1;
+                    tracing::info!("policies reloaded: version {} ({})", set.version, set.hash);
+                    *store().await.lock().await = Arc::new(set);
+                    last_error = None;
+                }
+                Ok(_) => last_error = None,
+                Err(e) => {
+                    let msg = format!("{:#}", e);
+                    if last_error.as_ref() != Some(&msg) {
+                        tracing::error!(
+                            "policy reload rejected, keeping version {}: {}",
+                            active.version,
+                            msg
+                        );
+                    }
+                    last_error = Some(msg);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_policies_validate_and_bad_edits_are_named() {
+        let set = PolicySet::bundled();
+        assert_eq!(set.default_policy.tiny_diff_loc, 120);
+        assert!(set.caps.require_approval("network"));
+        assert_eq!(set.hash.len(), 64);
+
+        let mut sources: Vec<(&str, String)> = FILES
+            .iter()
+            .map(|(name, text)| (*name, text.to_string()))
+            .collect();
+        sources[2].1 = sources[2].1.replace("sla_minutes: 60", "sla_minutes: 0");
+        let err = PolicySet::parse(&sources, "test").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "LANES.yaml: lanes[0].sla_minutes must be > 0"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use super::types::{Bits, RunCtx};
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::sync::{broadcast, mpsc, Mutex, OnceCell};
+use utoipa::ToSchema;
+
+/// Run phases. The middle five mirror the atomic steps in
+/// `policies/BOUNDARIES.yaml`; the rest bracket a run's lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Queued,
+    Start,
+    FetchInputs,
+    PlanActions,
+    VerifyDryRun,
+    ActApply,
+    VerifyPost,
+    Paused,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GateDecision {
+    pub gate: String,
+    pub passed: bool,
+    pub detail: Option<String>,
+}
+
+impl GateDecision {
+    pub fn new(gate: &str, passed: bool, detail: Option<String>) -> Self {
+        Self {
+            gate: gate.to_string(),
+            passed,
+            detail,
+        }
+    }
+}
+
+/// One progress beacon; serialized as the SSE `data` payload.
+/// Mirrors `schemas/PROGRESS_EVENT.schema.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ProgressEvent {
+    /// Monotonic bus sequence number, assigned on publish; used as the SSE id.
+    #[serde(default)]
+    pub id: u64,
+    pub run_id: String,
+    pub tenant: Option<String>,
+    pub phase: Phase,
+    pub ts: String,
+    pub bits: Option<Bits>,
+    #[serde(default)]
+    pub gates: Vec<GateDecision>,
+}
+
+impl ProgressEvent {
+    /// Tenants only see their own runs. Unauthenticated runs are seen by
+    /// whoever follows them by `run_id`, which only their submitter was given.
+    pub fn visible_to(&self, tenant: Option<&str>, run_id: Option<&str>) -> bool {
+        let owned = match &self.tenant {
+            Some(owner) => tenant == Some(owner.as_str()),
+            None => run_id.is_some(),
+        };
+        owned && run_id.is_none_or(|id| id == self.run_id)
+    }
+}
+
+const REPLAY_CAPACITY: usize = 1024;
+
+struct ProgressBus {
+    tx: broadcast::Sender<ProgressEvent>,
+    // Recent events for Last-Event-ID replay and lag recovery. Publishing
+    // happens under this lock so ids reach the buffer and the channel in order.
+    replay: Mutex<ReplayBuffer>,
+}
+
+struct ReplayBuffer {
+    next_id: u64,
+    events: VecDeque<ProgressEvent>,
+}
+
+impl ReplayBuffer {
+    fn since(&self, last_id: u64) -> Vec<ProgressEvent> {
+        self.events
+            .iter()
+            .filter(|e| e.id > last_id)
+            .cloned()
+            .collect()
+    }
+}
+
+static PROGRESS_BUS: OnceCell<ProgressBus> = OnceCell::const_new();
+
+async fn progress_bus() -> &'static ProgressBus {
+    PROGRESS_BUS
+        .get_or_init(|| async {
+            let (tx, _rx) = broadcast::channel(100);
+            ProgressBus {
+                tx,
+                replay: Mutex::new(ReplayBuffer {
+                    next_id: 1,
+                    events: VecDeque::with_capacity(REPLAY_CAPACITY),
+                }),
+            }
+        })
+        .await
+}
+
+pub async fn publish(mut event: ProgressEvent) {
+    let bus = progress_bus().await;
+    let mut replay = bus.replay.lock().await;
+    event.id = replay.next_id;
+    replay.next_id += 1;
+    if replay.events.len() == REPLAY_CAPACITY {
+        replay.events.pop_front();
+    }
+    replay.events.push_back(event.clone());
+    // No subscribers is not an error; the event stays in the replay buffer.
+    let _ = bus.tx.send(event);
+}
+
+/// Live events visible to `tenant` (of run `run_id` only, if given),
+/// starting with any buffered events newer than `last_id`. Events missed
+/// because the subscriber lagged behind the channel are recovered from the
+/// replay buffer, so ids stay gap-free as long as the client keeps up with
+/// `REPLAY_CAPACITY`. Forwarding stops once the receiver is dropped.
+pub async fn subscribe(
+    tenant: Option<String>,
+    run_id: Option<String>,
+    last_id: Option<u64>,
+) -> mpsc::Receiver<ProgressEvent> {
+    let bus = progress_bus().await;
+    let (out, rx) = mpsc::channel(64);
+    let (mut live, backlog, mut seen) = {
+        let replay = bus.replay.lock().await;
+        let live = bus.tx.subscribe();
+        let seen = replay.next_id - 1;
+        let backlog = last_id.map(|id| replay.since(id)).unwrap_or_default();
+        (live, backlog, seen)
+    };
+    tokio::spawn(async move {
+        let visible =
+            |event: &ProgressEvent| event.visible_to(tenant.as_deref(), run_id.as_deref());
+        for event in backlog {
+            if visible(&event) && out.send(event).await.is_err() {
+                return;
+            }
+        }
+        loop {
+            // A quiet stream would otherwise keep a gone client's task alive.
+            let received = tokio::select! {
+                received = live.recv() => received,
+                _ = out.closed() => return,
+            };
+            let batch = match received {
+                Ok(event) => vec![event],
+                Err(broadcast::error::RecvError::Lagged(_)) => bus.replay.lock().await.since(seen),
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            for event in batch {
+                if event.id <= seen {
+                    continue;
+                }
+                seen = event.id;
+                if visible(&event) && out.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Publish a lifecycle event that carries no bits (queued, cancelled, ...).
+pub async fn emit(ctx: &RunCtx, phase: Phase) {
+    publish(ProgressEvent {
+        id: 0,
+        run_id: ctx.run_id.clone(),
+        tenant: ctx.tenant.clone(),
+        phase,
+        ts: Utc::now().to_rfc3339(),
+        bits: None,
+        gates: Vec::new(),
+    })
+    .await;
+}
+
+/// Publish a boundary event with the current bits and any gate decisions
+/// taken during the phase.
+pub async fn emit_bits<B: Clone + Into<Bits>>(
+    ctx: &RunCtx,
+    phase: Phase,
+    bits: &B,
+    gates: Vec<GateDecision>,
+) {
+    publish(ProgressEvent {
+        id: 0,
+        run_id: ctx.run_id.clone(),
+        tenant: ctx.tenant.clone(),
+        phase,
+        ts: Utc::now().to_rfc3339(),
+        bits: Some(bits.clone().into()),
+        gates,
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn event(run_id: &str, tenant: Option<&str>) -> ProgressEvent {
+        ProgressEvent {
+            id: 0,
+            run_id: run_id.to_string(),
+            tenant: tenant.map(str::to_string),
+            phase: Phase::Start,
+            ts: Utc::now().to_rfc3339(),
+            bits: None,
+            gates: Vec::new(),
+        }
+    }
+
+    async fn next(rx: &mut mpsc::Receiver<ProgressEvent>) -> ProgressEvent {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("event in time")
+            .expect("stream open")
+    }
+
+    #[test]
+    fn anonymous_runs_are_visible_to_whoever_follows_them() {
+        let owned = event("r1", Some("demo"));
+        assert!(owned.visible_to(Some("demo"), None));
+        assert!(owned.visible_to(Some("demo"), Some("r1")));
+        assert!(!owned.visible_to(Some("demo"), Some("r2")));
+        assert!(!owned.visible_to(Some("premium"), Some("r1")));
+        assert!(!owned.visible_to(None, Some("r1")));
+
+        let anonymous = event("r1", None);
+        assert!(anonymous.visible_to(None, Some("r1")));
+        assert!(anonymous.visible_to(Some("demo"), Some("r1")));
+        assert!(!anonymous.visible_to(Some("demo"), None));
+        assert!(!anonymous.visible_to(None, Some("r2")));
+    }
+
+    #[tokio::test]
+    async fn last_event_id_replays_only_newer_events() {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        for _ in 0..3 {
+            publish(event(&run_id, Some("demo"))).await;
+        }
+        let ids: Vec<u64> = progress_bus()
+            .await
+            .replay
+            .lock()
+            .await
+            .events
+            .iter()
+            .filter(|e| e.run_id == run_id)
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids.len(), 3);
+
+        let mut rx = subscribe(Some("demo".into()), Some(run_id.clone()), Some(ids[0])).await;
+        assert_eq!(next(&mut rx).await.id, ids[1]);
+        assert_eq!(next(&mut rx).await.id, ids[2]);
+        publish(event(&run_id, Some("demo"))).await;
+        assert!(next(&mut rx).await.id > ids[2]);
+    }
+
+    #[tokio::test]
+    async fn a_lagging_subscriber_recovers_missed_events_in_order() {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let mut rx = subscribe(None, Some(run_id.clone()), None).await;
+        // Far more than the forwarding buffer plus the broadcast channel
+        // hold, so the subscriber lags and must catch up from the replay.
+        let total = 300;
+        for _ in 0..total {
+            publish(event(&run_id, None)).await;
+        }
+        let mut last = 0;
+        for _ in 0..total {
+            let e = next(&mut rx).await;
+            assert_eq!(e.run_id, run_id);
+            assert!(e.id > last, "ids must increase without repeats");
+            last = e.id;
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Isolated kernel state for validation runs. A run whose `RunCtx` carries
+//! a sandbox reads and adapts the sandbox's copy of the kernel, KPI and
+//! trace history instead of the process-wide ones, keeps its checkpoints
+//! here rather than in the run store (and writes no trace file), and its LM
+//! calls go to a mock provider seeded by `seed`, so live traffic never sees
+//! validation and the same seed gives the same results.
+
+use super::checkpoint::RunState;
+use super::kernel::{ExtendedBits, KernelLoop};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+#[derive(Debug)]
+pub struct Sandbox {
+    pub seed: u64,
+    pub(super) kernel: Mutex<KernelLoop>,
+    pub(super) kpi_history: Mutex<Vec<f32>>,
+    pub(super) trace_history: Mutex<Vec<ExtendedBits>>,
+    pub(super) runs: Mutex<HashMap<String, RunState>>,
+}
+
+impl Sandbox {
+    /// A sandbox starting from `kernel` (usually a snapshot of the live one).
+    pub fn new(kernel: KernelLoop, seed: u64) -> Self {
+        Self {
+            seed,
+            kernel: Mutex::new(kernel),
+            kpi_history: Mutex::new(Vec::new()),
+            trace_history: Mutex::new(Vec::new()),
+            runs: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Deterministic draw in [0, 1) for (`seed`, `key`, `salt`).
+pub fn unit(seed: u64, key: &str, salt: u64) -> f64 {
+    // FNV-1a over the key, then a splitmix64 finaliser.
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in key.bytes() {
+        h = (h ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    let mut z = h ^ seed.wrapping_add(salt.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_are_seeded_and_in_range() {
+        let a = unit(7, "hello", 0);
+        assert_eq!(a, unit(7, "hello", 0));
+        assert_ne!(a, unit(8, "hello", 0));
+        assert_ne!(a, unit(7, "hello", 1));
+        assert!((0..1000).all(|s| (0.0..1.0).contains(&unit(s, "x", 0))));
+    }
+}
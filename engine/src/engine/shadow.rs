@@ -0,0 +1,399 @@
+//! Shadow A/B evaluation of candidate L2 params. While a shadow is running,
+//! a `pct` sample of live runs is observed when it stops (paused or done):
+//! the live outcome comes from the `ask_act` and `evidence` decisions the
+//! run actually took, and the candidate's from taking those same gates
+//! again under the candidate params, on the bits each gate saw at the time
+//! — decisions only, nothing is acted on. The blocked/allowed/escalated
+//! rates can then be compared before promotion.
+
+use super::checkpoint::RunState;
+use super::expr::Expr;
+use super::kernel::{ExtendedBits, KernelLoop, L2Params, Meta2Proposal};
+use super::types::RunCtx;
+use super::{policies, sandbox};
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::{Mutex, OnceCell};
+use utoipa::ToSchema;
+
+/// Observations listed in the report; the rates cover every sampled run.
+const RECENT: usize = 100;
+const MAX_BACKOFF_K: u32 = 10;
+
+/// The decision path the gates give a run's bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Both gates open: the run acts directly.
+    Allowed,
+    /// Ask-act open but the evidence gate closed: verification first.
+    Escalated,
+    /// Ask-act closed: the run pauses for clarification.
+    Blocked,
+}
+
+/// The outcome `kernel` gives a run whose `ask_act` gate saw `ask_bits`
+/// and whose pre-act evidence gate saw `evidence_bits`.
+pub fn decide(
+    kernel: &KernelLoop,
+    act_if: &Expr,
+    ask_bits: &ExtendedBits,
+    evidence_bits: &ExtendedBits,
+) -> Outcome {
+    if !kernel.ask_act_gate(act_if, ask_bits) {
+        Outcome::Blocked
+    } else if !kernel.evidence_gate_before_act(evidence_bits) {
+        Outcome::Escalated
+    } else {
+        Outcome::Allowed
+    }
+}
+
+/// The outcome the live gates gave the run; `None` if it stopped before
+/// they decided.
+fn live_outcome(state: &RunState) -> Option<Outcome> {
+    match (state.gate_seen("ask_act"), state.gate_seen("evidence")) {
+        (Some(ask), _) if !ask.open => Some(Outcome::Blocked),
+        (Some(_), Some(evidence)) if !evidence.open => Some(Outcome::Escalated),
+        (Some(_), Some(_)) => Some(Outcome::Allowed),
+        _ => None,
+    }
+}
+
+/// Reject candidates the gates cannot sensibly run on.
+pub fn validate(params: &L2Params) -> anyhow::Result<()> {
+    let unit = |name: &str, v: f32| {
+        if v.is_finite() && (0.0..=1.0).contains(&v) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{} must be within 0..=1, got {}", name, v))
+        }
+    };
+    unit("confidence_gate_tau", params.confidence_gate_tau)?;
+    unit("ask_act_threshold", params.ask_act_threshold)?;
+    if !(1..=MAX_BACKOFF_K).contains(&params.backoff_k) {
+        anyhow::bail!(
+            "backoff_k must be within 1..={}, got {}",
+            MAX_BACKOFF_K,
+            params.backoff_k
+        );
+    }
+    if params.retry_strategies.iter().any(|s| s.trim().is_empty()) {
+        anyhow::bail!("retry_strategies must not contain empty names");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Observation {
+    pub run_id: String,
+    pub goal_id: String,
+    pub ts: String,
+    pub current: Outcome,
+    pub candidate: Outcome,
+}
+
+/// Outcome rates over `n` runs (fractions; differences for a delta).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Rates {
+    pub n: u64,
+    pub blocked: f64,
+    pub allowed: f64,
+    pub escalated: f64,
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    blocked: u64,
+    allowed: u64,
+    escalated: u64,
+}
+
+impl Tally {
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Blocked => self.blocked += 1,
+            Outcome::Allowed => self.allowed += 1,
+            Outcome::Escalated => self.escalated += 1,
+        }
+    }
+
+    fn rates(&self) -> Rates {
+        let n = self.blocked + self.allowed + self.escalated;
+        let rate = |k: u64| if n == 0 { 0.0 } else { k as f64 / n as f64 };
+        Rates {
+            n,
+            blocked: rate(self.blocked),
+            allowed: rate(self.allowed),
+            escalated: rate(self.escalated),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shadow {
+    id: String,
+    started_at: String,
+    candidate: L2Params,
+    pct: f32,
+    proposal: Option<Meta2Proposal>,
+    /// Latest observation per run, so a resumed run counts once.
+    runs: HashMap<String, Observation>,
+}
+
+impl Shadow {
+    /// Runs are sampled by id, so a resumed run gets the same answer.
+    fn samples(&self, run_id: &str) -> bool {
+        sandbox::unit(0, run_id, 0) < self.pct as f64
+    }
+
+    fn report(&self, live: &L2Params) -> ShadowReport {
+        let (mut current, mut candidate) = (Tally::default(), Tally::default());
+        for o in self.runs.values() {
+            current.add(o.current);
+            candidate.add(o.candidate);
+        }
+        let (current, candidate) = (current.rates(), candidate.rates());
+        let mut recent: Vec<Observation> = self.runs.values().cloned().collect();
+        recent.sort_by(|a, b| a.ts.cmp(&b.ts));
+        let recent = recent.split_off(recent.len().saturating_sub(RECENT));
+        ShadowReport {
+            id: self.id.clone(),
+            started_at: self.started_at.clone(),
+            pct: self.pct,
+            current_params: live.clone(),
+            candidate_params: self.candidate.clone(),
+            proposal: self.proposal.clone(),
+            current,
+            candidate,
+            delta: Rates {
+                n: current.n,
+                blocked: candidate.blocked - current.blocked,
+                allowed: candidate.allowed - current.allowed,
+                escalated: candidate.escalated - current.escalated,
+            },
+            disagreements: self
+                .runs
+                .values()
+                .filter(|o| o.current != o.candidate)
+                .count() as u64,
+            recent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ShadowReport {
+    pub id: String,
+    pub started_at: String,
+    /// Fraction of live runs sampled.
+    pub pct: f32,
+    pub current_params: L2Params,
+    pub candidate_params: L2Params,
+    /// The proposal the candidate comes from, if any.
+    pub proposal: Option<Meta2Proposal>,
+    /// Outcomes of the sampled runs under the live params.
+    pub current: Rates,
+    /// Counterfactual outcomes of the same runs under the candidate.
+    pub candidate: Rates,
+    /// candidate − current.
+    pub delta: Rates,
+    /// Sampled runs whose outcome would have changed.
+    pub disagreements: u64,
+    /// Latest observations, oldest first.
+    pub recent: Vec<Observation>,
+}
+
+static SHADOW: OnceCell<Mutex<Option<Shadow>>> = OnceCell::const_new();
+
+async fn shadow() -> &'static Mutex<Option<Shadow>> {
+    SHADOW.get_or_init(|| async { Mutex::new(None) }).await
+}
+
+impl Shadow {
+    fn new(candidate: L2Params, pct: f32, proposal: Option<Meta2Proposal>) -> Self {
+        Self {
+            id: format!("s-{}", uuid::Uuid::new_v4()),
+            started_at: Utc::now().to_rfc3339(),
+            candidate,
+            pct: pct.clamp(0.0, 1.0),
+            proposal,
+            runs: HashMap::new(),
+        }
+    }
+}
+
+/// Start shadowing `candidate` on a `pct` sample of live runs, replacing
+/// any running shadow. Refused if the candidate fails [`validate`].
+pub async fn start(
+    candidate: L2Params,
+    pct: f32,
+    proposal: Option<Meta2Proposal>,
+) -> anyhow::Result<ShadowReport> {
+    validate(&candidate)?;
+    if !pct.is_finite() {
+        anyhow::bail!("pct must be a number within 0..=1");
+    }
+    let state = Shadow::new(candidate, pct, proposal);
+    let report = state.report(&super::kernel_snapshot().await.l2_params);
+    tracing::info!(
+        "Shadowing L2 params {} on {:.0}% of runs",
+        report.id,
+        report.pct * 100.0
+    );
+    *shadow().await.lock().await = Some(state);
+    Ok(report)
+}
+
+/// Shadow a fresh proposal at its own `shadow_pct`, unless a shadow is
+/// already running.
+pub async fn start_for_proposal(proposal: &Meta2Proposal) {
+    let live = super::kernel_snapshot().await.l2_params;
+    let mut guard = shadow().await.lock().await;
+    let candidate = proposal.change.apply(&live);
+    if let Err(e) = validate(&candidate) {
+        tracing::warn!("Not shadowing proposal {}: {}", proposal.symptom, e);
+        return;
+    }
+    if guard.is_none() {
+        let state = Shadow::new(candidate, proposal.shadow_pct, Some(proposal.clone()));
+        tracing::info!("Shadowing proposal as {}: {}", state.id, proposal.symptom);
+        *guard = Some(state);
+    }
+}
+
+pub async fn report() -> Option<ShadowReport> {
+    let live = super::kernel_snapshot().await.l2_params;
+    shadow()
+        .await
+        .lock()
+        .await
+        .as_ref()
+        .map(|s| s.report(&live))
+}
+
+/// End the shadow without promoting; returns its final report.
+pub async fn stop() -> Option<ShadowReport> {
+    let live = super::kernel_snapshot().await.l2_params;
+    shadow().await.lock().await.take().map(|s| s.report(&live))
+}
+
+/// Make the candidate the live params and end the shadow. Refused until
+/// at least one run has been observed, or if the candidate is invalid.
+pub async fn promote() -> anyhow::Result<ShadowReport> {
+    let state = {
+        let mut guard = shadow().await.lock().await;
+        match guard.as_ref() {
+            None => anyhow::bail!("no shadow evaluation is running"),
+            Some(s) if s.runs.is_empty() => {
+                anyhow::bail!("shadow {} has not observed any runs yet", s.id)
+            }
+            Some(s) => validate(&s.candidate)?,
+        }
+        guard.take().expect("checked above")
+    };
+    let mut kernel = super::live_kernel().await.lock().await;
+    let report = state.report(&kernel.l2_params);
+    kernel.l2_params = state.candidate;
+    tracing::info!(
+        "Promoted shadow {} after {} runs ({} disagreements)",
+        report.id,
+        report.current.n,
+        report.disagreements
+    );
+    Ok(report)
+}
+
+/// Record the live outcome of a sampled live run and the candidate's on the
+/// bits its gates saw. Sandboxed and taped (golden) runs are not live
+/// traffic and are skipped, as are runs that stopped before the gates.
+pub async fn observe(ctx: &RunCtx, state: &RunState) {
+    if ctx.sandbox.is_some() || ctx.tape.is_some() {
+        return;
+    }
+    let (Some(current), Some(ask)) = (live_outcome(state), state.gate_seen("ask_act")) else {
+        return;
+    };
+    let (id, candidate) = match &*shadow().await.lock().await {
+        Some(s) if s.samples(&state.run_id) => (s.id.clone(), s.candidate.clone()),
+        _ => return,
+    };
+    // A run blocked live never reached the evidence gate; nothing changes
+    // its bits in between, so the candidate's would have seen the same.
+    let evidence_bits = state.gate_seen("evidence").map_or(&ask.bits, |g| &g.bits);
+    let act_if = policies::current().await.ask_act.act_if.clone();
+    let mut kernel = super::kernel_snapshot().await;
+    kernel.l2_params = candidate;
+    let candidate = decide(&kernel, &act_if, &ask.bits, evidence_bits);
+
+    let mut guard = shadow().await.lock().await;
+    if let Some(s) = guard.as_mut().filter(|s| s.id == id) {
+        s.runs.insert(
+            state.run_id.clone(),
+            Observation {
+                run_id: state.run_id.clone(),
+                goal_id: state.goal_id.clone(),
+                ts: Utc::now().to_rfc3339(),
+                current,
+                candidate,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_lower_tau_escalates_uncertain_runs_before_they_act() {
+        let mut kernel = KernelLoop::new();
+        let act_if = Expr::parse("A>=1 && P>=1").unwrap();
+        // As the gates see it before acting: E has not been set yet.
+        let mut bits = ExtendedBits::init();
+        bits.u = 0.7;
+        kernel.l2_params.confidence_gate_tau = 0.75;
+        assert_eq!(decide(&kernel, &act_if, &bits, &bits), Outcome::Allowed);
+        kernel.l2_params.confidence_gate_tau = 0.65;
+        assert_eq!(decide(&kernel, &act_if, &bits, &bits), Outcome::Escalated);
+        bits.p = 0.0;
+        assert_eq!(decide(&kernel, &act_if, &bits, &bits), Outcome::Blocked);
+
+        // The live outcome is what the run's gates decided, whatever its
+        // bits became afterwards.
+        let ctx = RunCtx::new(None);
+        let policy = crate::engine::types::Policy {
+            gamma_gate: 0.5,
+            time_ms: 1000,
+            max_risk: 0.5,
+            tiny_diff_loc: 50,
+        };
+        let mut state = RunState::new(&ctx, "g", serde_json::json!({}), &policy);
+        assert_eq!(live_outcome(&state), None);
+        state.saw("ask_act", true, &bits);
+        state.saw("evidence", false, &bits);
+        state.bits.e = 1.0;
+        assert_eq!(live_outcome(&state), Some(Outcome::Escalated));
+
+        let mut tally = Tally::default();
+        tally.add(Outcome::Allowed);
+        tally.add(Outcome::Escalated);
+        let rates = tally.rates();
+        assert_eq!((rates.n, rates.allowed, rates.blocked), (2, 0.5, 0.0));
+    }
+
+    #[test]
+    fn rejects_out_of_range_candidates() {
+        let mut params = KernelLoop::new().l2_params;
+        assert!(validate(&params).is_ok());
+        params.confidence_gate_tau = f32::NAN;
+        assert!(validate(&params).is_err());
+        params.confidence_gate_tau = 1.5;
+        assert!(validate(&params).is_err());
+        params.confidence_gate_tau = 0.7;
+        params.backoff_k = 0;
+        assert!(validate(&params).is_err());
+    }
+}
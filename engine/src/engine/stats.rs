@@ -0,0 +1,180 @@
+//! Small-sample statistics for comparing validation runs: summaries and
+//! Welch's t-test with Student-t p-values.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: f64,
+    /// Sample standard deviation; 0 with fewer than two values.
+    pub sd: f64,
+}
+
+pub fn summarize(xs: &[f64]) -> Summary {
+    let n = xs.len();
+    let mean = if n == 0 {
+        0.0
+    } else {
+        xs.iter().sum::<f64>() / n as f64
+    };
+    let sd = if n < 2 {
+        0.0
+    } else {
+        (xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+    };
+    Summary { n, mean, sd }
+}
+
+/// A mean with its two-sided confidence interval; the bounds are `None`
+/// with fewer than two values.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Interval {
+    pub n: usize,
+    pub mean: f64,
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+}
+
+/// Student-t confidence interval of the mean at `level` (e.g. 0.95).
+pub fn interval(xs: &[f64], level: f64) -> Interval {
+    let s = summarize(xs);
+    let half = (s.n >= 2).then(|| {
+        let df = (s.n - 1) as f64;
+        t_quantile((1.0 - level) / 2.0, df) * s.sd / (s.n as f64).sqrt()
+    });
+    Interval {
+        n: s.n,
+        mean: s.mean,
+        lower: half.map(|h| s.mean - h),
+        upper: half.map(|h| s.mean + h),
+    }
+}
+
+/// The t with upper-tail probability `tail` at `df` degrees of freedom.
+pub fn t_quantile(tail: f64, df: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0, 1e3);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if student_t_sf(mid, df) > tail {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+/// Two-sided p-value of Welch's t-test that `a` and `b` share a mean.
+/// `None` with fewer than two values on either side; identical constant
+/// samples give 1, different constant samples 0.
+pub fn welch_p(a: &[f64], b: &[f64]) -> Option<f64> {
+    let (a, b) = (summarize(a), summarize(b));
+    if a.n < 2 || b.n < 2 {
+        return None;
+    }
+    let (va, vb) = (a.sd.powi(2) / a.n as f64, b.sd.powi(2) / b.n as f64);
+    let se2 = va + vb;
+    if se2 == 0.0 {
+        return Some(if a.mean == b.mean { 1.0 } else { 0.0 });
+    }
+    let t = (a.mean - b.mean) / se2.sqrt();
+    let df = se2.powi(2) / (va.powi(2) / (a.n - 1) as f64 + vb.powi(2) / (b.n - 1) as f64);
+    Some(2.0 * student_t_sf(t.abs(), df))
+}
+
+/// P(T > t) for Student's t with `df` degrees of freedom, t ≥ 0.
+pub fn student_t_sf(t: f64, df: f64) -> f64 {
+    0.5 * incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// Regularized incomplete beta I_x(a, b).
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_cf(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_cf(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction for the incomplete beta (modified Lentz).
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for aa in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + aa * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + aa / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Lanczos approximation of ln Γ(x) for x > 0.
+fn ln_gamma(x: f64) -> f64 {
+    const G: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series: f64 = 1.000_000_000_190_015
+        + G.iter()
+            .enumerate()
+            .map(|(i, g)| g / (x + 1.0 + i as f64))
+            .sum::<f64>();
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_tail_and_welch_match_reference_values() {
+        // t = 2.228 is the two-sided 5% critical value at 10 df.
+        assert!((2.0 * student_t_sf(2.228, 10.0) - 0.05).abs() < 1e-3);
+        let p = welch_p(&[0.9, 0.85, 0.88, 0.91], &[0.6, 0.62, 0.58, 0.61]).unwrap();
+        assert!(p < 0.001);
+        assert_eq!(welch_p(&[0.5], &[0.1, 0.2]), None);
+        assert_eq!(welch_p(&[0.5, 0.5], &[0.5, 0.5]), Some(1.0));
+        assert!((t_quantile(0.025, 10.0) - 2.228).abs() < 1e-3);
+        let ci = interval(&[1.0, 2.0, 3.0], 0.95);
+        assert!((ci.upper.unwrap() - 4.484).abs() < 1e-3);
+        assert_eq!(interval(&[1.0], 0.95).lower, None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Validation suites: one YAML or JSON file per suite in `SUITES_DIR`
+//! (default `suites/`), re-read on every request so new suites need no
+//! rebuild. The bundled copies stand in when the directory is missing.
+
+use super::types::{Bits, Policy};
+use anyhow::Context;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+const DEFAULT_DIR: &str = "suites";
+/// Most runs per task a suite file or `/validate` request may ask for.
+pub const MAX_REPEAT: u32 = 100;
+const BIT_NAMES: [&str; 9] = ["A", "U", "P", "E", "Δ", "I", "R", "T", "M"];
+
+const BUNDLED: [(&str, &str); 4] = [
+    ("adaptive.yaml", include_str!("../../suites/adaptive.yaml")),
+    ("easy.yaml", include_str!("../../suites/easy.yaml")),
+    ("hard.yaml", include_str!("../../suites/hard.yaml")),
+    (
+        "impossible.yaml",
+        include_str!("../../suites/impossible.yaml"),
+    ),
+];
+
+/// Inclusive `[min, max]` per bit name (`A`…`M`, drift as `Δ`).
+pub type BitRanges = BTreeMap<String, [f32; 2]>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suite {
+    /// Defaults to the file stem.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Runs per task unless the task overrides it.
+    #[serde(default = "one")]
+    pub repeat: u32,
+    /// Defaults to the policy set's `default_policy`.
+    #[serde(default)]
+    pub policy: Option<Policy>,
+    /// Bits ranges every task is expected to end within.
+    #[serde(default)]
+    pub expect: BitRanges,
+    pub tasks: Vec<SuiteTask>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteTask {
+    pub goal_id: String,
+    pub difficulty: f32,
+    #[serde(default)]
+    pub inputs: Value,
+    #[serde(default)]
+    pub repeat: Option<u32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Merged over the suite's `expect`.
+    #[serde(default)]
+    pub expect: BitRanges,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Suite {
+    pub fn repeats(&self, task: &SuiteTask) -> u32 {
+        task.repeat.unwrap_or(self.repeat)
+    }
+
+    /// The suite's ranges with the task's own on top.
+    pub fn expectations(&self, task: &SuiteTask) -> BitRanges {
+        let mut ranges = self.expect.clone();
+        ranges.extend(task.expect.clone());
+        ranges
+    }
+
+    fn validate(&self, file: &str) -> anyhow::Result<()> {
+        if self.tasks.is_empty() {
+            anyhow::bail!("{}: tasks must not be empty", file);
+        }
+        if !(1..=MAX_REPEAT).contains(&self.repeat) {
+            anyhow::bail!("{}: repeat must be within 1..={}", file, MAX_REPEAT);
+        }
+        check_ranges(file, "expect", &self.expect)?;
+        for (i, task) in self.tasks.iter().enumerate() {
+            if !(0.0..=1.0).contains(&task.difficulty) {
+                anyhow::bail!(
+                    "{}: tasks[{}].difficulty must be within [0, 1], got {}",
+                    file,
+                    i,
+                    task.difficulty
+                );
+            }
+            if task.repeat.is_some_and(|r| !(1..=MAX_REPEAT).contains(&r)) {
+                anyhow::bail!(
+                    "{}: tasks[{}].repeat must be within 1..={}",
+                    file,
+                    i,
+                    MAX_REPEAT
+                );
+            }
+            check_ranges(file, &format!("tasks[{}].expect", i), &task.expect)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_ranges(file: &str, field: &str, ranges: &BitRanges) -> anyhow::Result<()> {
+    for (bit, [min, max]) in ranges {
+        if !BIT_NAMES.contains(&bit.as_str()) {
+            anyhow::bail!("{}: {} has unknown bit `{}`", file, field, bit);
+        }
+        if !(0.0..=1.0).contains(min) || !(0.0..=1.0).contains(max) || min > max {
+            anyhow::bail!(
+                "{}: {}.{} must be [min, max] within [0, 1], got [{}, {}]",
+                file,
+                field,
+                bit,
+                min,
+                max
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ranges in `expect` that `bits` falls outside of.
+pub fn violations(expect: &BitRanges, bits: &Bits) -> Vec<String> {
+    expect
+        .iter()
+        .filter_map(|(bit, [min, max])| {
+            let v = match bit.as_str() {
+                "A" => bits.a,
+                "U" => bits.u,
+                "P" => bits.p,
+                "E" => bits.e,
+                "Δ" => bits.d,
+                "I" => bits.i,
+                "R" => bits.r,
+                "T" => bits.t,
+                "M" => bits.m,
+                _ => return None,
+            };
+            (v < *min || v > *max)
+                .then(|| format!("{}={:.2} outside [{:.2}, {:.2}]", bit, v, min, max))
+        })
+        .collect()
+}
+
+/// A suite as listed by `GET /validate/suites`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SuiteInfo {
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub tasks: usize,
+    /// Task runs in total, counting repeats.
+    pub runs: u32,
+    /// File it was loaded from, or `bundled`.
+    pub source: String,
+}
+
+impl From<&(Suite, String)> for SuiteInfo {
+    fn from((suite, source): &(Suite, String)) -> Self {
+        Self {
+            name: suite.name.clone(),
+            description: suite.description.clone(),
+            tags: suite.tags.clone(),
+            tasks: suite.tasks.len(),
+            runs: suite.tasks.iter().map(|t| suite.repeats(t)).sum(),
+            source: source.clone(),
+        }
+    }
+}
+
+pub fn dir() -> PathBuf {
+    PathBuf::from(std::env::var("SUITES_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string()))
+}
+
+/// Every suite with where it came from, sorted by name.
+pub fn load_all() -> anyhow::Result<Vec<(Suite, String)>> {
+    let dir = dir();
+    let from_dir = dir.is_dir();
+    let sources: Vec<(String, String)> = if from_dir {
+        read_dir(&dir)?
+    } else {
+        BUNDLED
+            .iter()
+            .map(|(name, text)| (name.to_string(), text.to_string()))
+            .collect()
+    };
+    let mut suites: Vec<(Suite, String)> = Vec::new();
+    for (file, text) in sources {
+        let suite = parse(&file, &text)?;
+        if let Some((_, other)) = suites.iter().find(|(s, _)| s.name == suite.name) {
+            anyhow::bail!(
+                "{}: suite `{}` is already defined in {}",
+                file,
+                suite.name,
+                other
+            );
+        }
+        let origin = if from_dir {
+            dir.join(&file).display().to_string()
+        } else {
+            "bundled".to_string()
+        };
+        suites.push((suite, origin));
+    }
+    suites.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+    Ok(suites)
+}
+
+pub fn load(name: &str) -> anyhow::Result<Suite> {
+    load_all()?
+        .into_iter()
+        .map(|(suite, _)| suite)
+        .find(|s| s.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown suite: {}", name))
+}
+
+fn read_dir(dir: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    let mut sources = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("cannot read {}", dir.display()))? {
+        let path = entry?.path();
+        let is_suite = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| matches!(e, "yaml" | "yml" | "json"));
+        if !is_suite {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let file = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        sources.push((file, text));
+    }
+    Ok(sources)
+}
+
+fn parse(file: &str, text: &str) -> anyhow::Result<Suite> {
+    let parsed = if file.ends_with(".json") {
+        serde_json::from_str(text).map_err(anyhow::Error::from)
+    } else {
+        serde_yaml::from_str(text).map_err(anyhow::Error::from)
+    };
+    let mut suite: Suite = parsed.with_context(|| format!("invalid {}", file))?;
+    if suite.name.is_empty() {
+        suite.name = file
+            .rsplit_once('.')
+            .map_or(file, |(stem, _)| stem)
+            .to_string();
+    }
+    suite.validate(file)?;
+    Ok(suite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_suites_parse_and_bad_ranges_are_named() {
+        for (file, text) in BUNDLED {
+            parse(file, text).unwrap();
+        }
+        let easy = parse("easy.yaml", BUNDLED[1].1).unwrap();
+        assert_eq!(easy.name, "easy");
+        assert_eq!(easy.tasks.len(), 3);
+
+        let bad = "tasks:\n  - goal_id: x\n    difficulty: 0.5\n    expect: {U: [0.8, 0.2]}\n";
+        assert_eq!(
+            parse("bad.yaml", bad).unwrap_err().to_string(),
+            "bad.yaml: tasks[0].expect.U must be [min, max] within [0, 1], got [0.8, 0.2]"
+        );
+        for repeat in ["repeat: 0\n", "repeat: 101\n"] {
+            let text = format!("{}tasks:\n  - goal_id: x\n    difficulty: 0.5\n", repeat);
+            assert_eq!(
+                parse("r.yaml", &text).unwrap_err().to_string(),
+                "r.yaml: repeat must be within 1..=100"
+            );
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Record/replay of a run's external interactions: LM responses and
+//! executor results. A recording tape captures them as the run makes them;
+//! a replay tape serves them back in the same order without touching the
+//! network or spawning processes, and fails the run as soon as it asks for
+//! something the recording does not have.
+
+use super::executor::{self, Action, ExecResult};
+use super::types::{Policy, RunCtx};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TapeEntry {
+    Lm { request: String, response: Value },
+    Exec { action: Action, result: ExecResult },
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record,
+    Replay { cursor: usize },
+}
+
+#[derive(Debug)]
+pub struct Tape {
+    mode: Mutex<Mode>,
+    entries: Mutex<Vec<TapeEntry>>,
+}
+
+impl Tape {
+    pub fn record() -> Self {
+        Self {
+            mode: Mutex::new(Mode::Record),
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn replay(entries: Vec<TapeEntry>) -> Self {
+        Self {
+            mode: Mutex::new(Mode::Replay { cursor: 0 }),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<TapeEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Entries a replay has not consumed.
+    pub fn remaining(&self) -> usize {
+        match *self.mode.lock().unwrap() {
+            Mode::Record => 0,
+            Mode::Replay { cursor } => self.entries.lock().unwrap().len() - cursor,
+        }
+    }
+
+    fn is_replay(&self) -> bool {
+        matches!(*self.mode.lock().unwrap(), Mode::Replay { .. })
+    }
+
+    fn push(&self, entry: TapeEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// The next recorded entry, if `matches` accepts it.
+    fn next(&self, what: &str, matches: impl Fn(&TapeEntry) -> bool) -> Result<TapeEntry> {
+        let mut mode = self.mode.lock().unwrap();
+        let Mode::Replay { cursor } = &mut *mode else {
+            bail!("tape is not replaying");
+        };
+        let entries = self.entries.lock().unwrap();
+        match entries.get(*cursor) {
+            Some(entry) if matches(entry) => {
+                *cursor += 1;
+                Ok(entry.clone())
+            }
+            Some(entry) => bail!(
+                "replay diverged at entry {}: run asked for {}, recording has {}",
+                *cursor,
+                what,
+                serde_json::to_string(entry).unwrap_or_default()
+            ),
+            None => bail!(
+                "replay diverged: run asked for {} past the end of the recording",
+                what
+            ),
+        }
+    }
+}
+
+/// Answer an LM `request` through the run's tape, or live via `call`.
+pub async fn lm<F>(ctx: &RunCtx, request: &str, call: F) -> Result<Value>
+where
+    F: Future<Output = Result<Value>>,
+{
+    let Some(tape) = &ctx.tape else {
+        return call.await;
+    };
+    if tape.is_replay() {
+        let entry = tape.next(
+            &format!("LM response to {:?}", request),
+            |e| matches!(e, TapeEntry::Lm { request: r, .. } if r == request),
+        )?;
+        let TapeEntry::Lm { response, .. } = entry else {
+            unreachable!("matched an LM entry");
+        };
+        return Ok(response);
+    }
+    let response = call.await?;
+    tape.push(TapeEntry::Lm {
+        request: request.to_string(),
+        response: response.clone(),
+    });
+    Ok(response)
+}
+
+/// Execute `action` through the run's tape, or live via the executor.
+pub async fn execute(ctx: &RunCtx, action: Action, policy: &Policy) -> Result<ExecResult> {
+    let Some(tape) = &ctx.tape else {
+        return executor::execute(action, policy).await;
+    };
+    if tape.is_replay() {
+        let Action::Cli(cmd) = &action;
+        let entry = tape.next(
+            &format!("executor output of {:?}", cmd),
+            |e| matches!(e, TapeEntry::Exec { action: Action::Cli(c), .. } if c == cmd),
+        )?;
+        let TapeEntry::Exec { result, .. } = entry else {
+            unreachable!("matched an executor entry");
+        };
+        return Ok(result);
+    }
+    let result = executor::execute(action.clone(), policy).await?;
+    tape.push(TapeEntry::Exec {
+        action,
+        result: result.clone(),
+    });
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn replay_serves_recording_and_detects_divergence() {
+        let mut ctx = RunCtx::new(None);
+        ctx.tape = Some(Arc::new(Tape::replay(vec![TapeEntry::Lm {
+            request: "hi".to_string(),
+            response: json!({"reply": "recorded"}),
+        }])));
+        let live = async { bail!("replay must not call the LM") };
+        let out = lm(&ctx, "hi", live).await.unwrap();
+        assert_eq!(out["reply"], "recorded");
+
+        let err = lm(&ctx, "again", async { Ok(json!({})) })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("past the end"));
+    }
+}
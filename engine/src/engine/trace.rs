@@ -0,0 +1,200 @@
+//! Per-run traces in the shape of `schemas/TRACE.schema.json`, appended to
+//! `<run store>/<run_id>.jsonl`: L1 records for actions, L2 for gate
+//! decisions and L3 for meta² proposals. `lane` is the boundary that
+//! produced the record, `cost` the milliseconds that boundary took and
+//! `eta` the number of boundaries still to run.
+
+use super::checkpoint::{self, Plan, RunState};
+use super::kernel::ExtendedBits;
+use super::progress::{GateDecision, Phase};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Layer {
+    L1,
+    L2,
+    L3,
+}
+
+/// Bits quantised to 0/1, as the trace schema requires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceBits {
+    #[serde(rename = "A")]
+    pub a: u8,
+    #[serde(rename = "U")]
+    pub u: u8,
+    #[serde(rename = "P")]
+    pub p: u8,
+    #[serde(rename = "E")]
+    pub e: u8,
+    #[serde(rename = "Δ")]
+    pub d: u8,
+    #[serde(rename = "I")]
+    pub i: u8,
+    #[serde(rename = "R")]
+    pub r: u8,
+    #[serde(rename = "T")]
+    pub t: u8,
+    #[serde(rename = "M")]
+    pub m: u8,
+}
+
+impl From<&ExtendedBits> for TraceBits {
+    fn from(b: &ExtendedBits) -> Self {
+        let q = |v: f32| u8::from(v >= 0.5);
+        Self {
+            a: q(b.a),
+            u: q(b.u),
+            p: q(b.p),
+            e: q(b.e),
+            d: q(b.d),
+            i: q(b.i),
+            r: q(b.r),
+            t: q(b.t),
+            m: q(b.m),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub ts: String,
+    pub layer: Layer,
+    pub obs: Value,
+    pub act: Value,
+    pub out: Value,
+    pub bits: TraceBits,
+    pub cost: f64,
+    pub eta: f64,
+    pub lane: String,
+}
+
+/// Records for one completed (or paused) boundary of `state`.
+pub fn records(
+    state: &RunState,
+    phase: Phase,
+    gates: &[GateDecision],
+    elapsed_ms: f64,
+) -> Vec<TraceRecord> {
+    let lane = serde_json::to_value(phase)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let eta = checkpoint::BOUNDARIES
+        .iter()
+        .filter(|p| **p != phase && !state.completed.contains(p))
+        .count() as f64;
+    let record = |layer, obs, act, out| TraceRecord {
+        ts: Utc::now().to_rfc3339(),
+        layer,
+        obs,
+        act,
+        out,
+        bits: (&state.bits).into(),
+        cost: elapsed_ms,
+        eta,
+        lane: lane.clone(),
+    };
+    let mut out = Vec::new();
+
+    if phase == Phase::ActApply {
+        let act = match &state.plan {
+            Some(Plan::Cli { action, .. }) => json!({ "action": action }),
+            Some(Plan::Chat { message }) => json!({ "chat": message }),
+            None => json!({}),
+        };
+        let result = match (&state.exec, &state.lm_result) {
+            (Some(exec), _) => json!(exec),
+            (None, Some(lm)) => json!({ "reply": lm.pointer("/manifest/evidence/reply") }),
+            (None, None) => json!({ "skipped": true }),
+        };
+        out.push(record(
+            Layer::L1,
+            json!({ "goal_id": state.goal_id, "approvals": state.approvals }),
+            act,
+            result,
+        ));
+    }
+
+    for gate in gates {
+        out.push(record(
+            Layer::L2,
+            json!({ "goal_id": state.goal_id }),
+            json!({ "gate": gate.gate }),
+            json!({ "passed": gate.passed, "detail": gate.detail }),
+        ));
+    }
+
+    if phase == Phase::VerifyPost {
+        if let Some(proposal) = &state.meta2_proposal {
+            out.push(record(
+                Layer::L3,
+                json!({ "symptom": proposal.symptom }),
+                json!({ "propose": proposal.change }),
+                json!(proposal),
+            ));
+        }
+    }
+    out
+}
+
+/// Append `records` to the run's trace file.
+pub async fn append(run_id: &str, records: &[TraceRecord]) -> anyhow::Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let path = checkpoint::run_path(run_id, "jsonl")?;
+    tokio::fs::create_dir_all(checkpoint::store_dir()).await?;
+    let mut buf = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut buf, record)?;
+        buf.push(b'\n');
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await?;
+    file.write_all(&buf).await?;
+    Ok(())
+}
+
+/// The raw JSONL trace of a run.
+pub async fn read(run_id: &str) -> anyhow::Result<String> {
+    let path = checkpoint::run_path(run_id, "jsonl")?;
+    Ok(tokio::fs::read_to_string(&path).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::{Policy, RunCtx};
+
+    #[test]
+    fn gate_records_carry_schema_fields() {
+        let policy = Policy {
+            gamma_gate: 0.5,
+            time_ms: 1000,
+            max_risk: 0.3,
+            tiny_diff_loc: 120,
+        };
+        let mut state = RunState::new(&RunCtx::new(None), "easy.test", json!({}), &policy);
+        state.completed.push(Phase::FetchInputs);
+        state.bits.u = 0.3;
+        state.bits.d = 0.7;
+        let gates = [GateDecision::new("ask_act", true, None)];
+        let records = records(&state, Phase::PlanActions, &gates, 2.5);
+
+        assert_eq!(records.len(), 1);
+        let record = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(record["layer"], "L2");
+        assert_eq!(record["lane"], "plan_actions");
+        assert_eq!(record["eta"], 3.0);
+        assert_eq!(record["bits"]["U"], 0);
+        assert_eq!(record["bits"]["Δ"], 1);
+        assert_eq!(record["act"]["gate"], "ask_act");
+    }
+}
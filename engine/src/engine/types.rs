@@ -1,8 +1,12 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
 use utoipa::ToSchema;
 
 pub use super::bits::Bits;
+use super::sandbox::Sandbox;
+use super::tape::Tape;
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct Policy {
@@ -20,3 +24,69 @@ pub struct Manifest {
     pub evidence: serde_json::Value,
     pub bits: Bits,
 }
+
+/// Identity of a single run: the id shared by its manifest and progress
+/// events, and the tenant it belongs to (`None` for unauthenticated runs).
+/// Interactive clients (the chat WebSocket) attach a `stream` to receive
+/// tokens as they are generated and to answer approval requests. A `tape`
+/// records or replays the run's LM and executor interactions; a `sandbox`
+/// isolates its kernel state and LM (validation runs).
+#[derive(Debug, Clone)]
+pub struct RunCtx {
+    pub run_id: String,
+    pub tenant: Option<String>,
+    pub stream: Option<mpsc::UnboundedSender<StreamEvent>>,
+    pub tape: Option<Arc<Tape>>,
+    pub sandbox: Option<Arc<Sandbox>>,
+}
+
+#[derive(Debug)]
+pub enum StreamEvent {
+    Token(String),
+    /// The tokens sent so far are void; the reply starts over.
+    Reset(String),
+    ProvisionalBits(Bits),
+    Approval(ApprovalRequest),
+}
+
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    pub capability: String,
+    pub action: String,
+    pub respond: oneshot::Sender<bool>,
+}
+
+impl RunCtx {
+    pub fn new(tenant: Option<String>) -> Self {
+        Self {
+            run_id: super::new_run_id(),
+            tenant,
+            stream: None,
+            tape: None,
+            sandbox: None,
+        }
+    }
+
+    pub fn send(&self, event: StreamEvent) {
+        if let Some(stream) = &self.stream {
+            let _ = stream.send(event);
+        }
+    }
+
+    /// Ask the attached client to approve `action` under `capability`.
+    /// `None` when no interactive client is attached; a client that goes
+    /// away without answering counts as a denial.
+    pub async fn request_approval(&self, capability: &str, action: &str) -> Option<bool> {
+        let stream = self.stream.as_ref()?;
+        let (respond, answer) = oneshot::channel();
+        let req = ApprovalRequest {
+            capability: capability.to_string(),
+            action: action.to_string(),
+            respond,
+        };
+        if stream.send(StreamEvent::Approval(req)).is_err() {
+            return Some(false);
+        }
+        Some(answer.await.unwrap_or(false))
+    }
+}
@@ -1,69 +1,160 @@
-use crate::api::{ValidateResp, ValidationResult};
+use crate::api::{TaskAggregate, ValidateReq, ValidateResp, ValidationResult};
 use crate::engine::{
-    self,
-    types::{Manifest, Policy},
+    self, calibration, history, policies,
+    sandbox::Sandbox,
+    stats,
+    suites::{self, Suite},
+    types::{Manifest, Policy, RunCtx},
 };
-use serde_json::json;
-
-pub async fn run_suite(suite: &str) -> anyhow::Result<ValidateResp> {
-    let policy = Policy {
-        gamma_gate: 0.5,
-        time_ms: 5000,
-        max_risk: 0.5,
-        tiny_diff_loc: 120,
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// Confidence level of the aggregate intervals.
+const CONFIDENCE: f64 = 0.95;
+
+/// Run a suite in sandboxes: repeat `r` of every task runs in lane `r`,
+/// lanes run concurrently, and each lane walks the tasks in suite order on
+/// its own copy of the live kernel with mock-LM seed `seed + r - 1`. The
+/// live kernel is never touched, and a given seed gives the same results.
+pub async fn run_suite(req: &ValidateReq) -> anyhow::Result<ValidateResp> {
+    if let Some(repeats) = req
+        .repeats
+        .filter(|r| !(1..=suites::MAX_REPEAT).contains(r))
+    {
+        anyhow::bail!(
+            "repeats must be within 1..={}, got {}",
+            suites::MAX_REPEAT,
+            repeats
+        );
+    }
+    let suite = Arc::new(suites::load(&req.suite)?);
+    let policy = match &suite.policy {
+        Some(policy) => policy.clone(),
+        None => policies::current().await.default_policy.clone(),
     };
-
-    let tasks = match suite {
-        "easy" => vec![
-            ("easy.echo1", 0.1, json!({"message": "test1"})),
-            ("easy.echo2", 0.1, json!({"message": "test2"})),
-            ("easy.echo3", 0.1, json!({"message": "test3"})),
-        ],
-        "hard" => vec![
-            ("hard.delay1", 0.7, json!({"message": "slow1"})),
-            ("hard.delay2", 0.7, json!({"message": "slow2"})),
-            ("hard.delay3", 0.7, json!({"message": "slow3"})),
-        ],
-        "impossible" => vec![
-            ("impossible.fail1", 0.9, json!({})),
-            ("impossible.fail2", 0.9, json!({})),
-            ("impossible.fail3", 0.9, json!({})),
-        ],
-        "adaptive" => vec![
-            ("easy.adapt1", 0.1, json!({"message": "adapt1"})),
-            ("hard.adapt2", 0.7, json!({"message": "adapt2"})),
-            ("impossible.adapt3", 0.9, json!({})),
-            ("easy.adapt4", 0.1, json!({"message": "adapt4"})), // Should have learned
-        ],
-        _ => return Err(anyhow::anyhow!("Unknown suite: {}", suite)),
+    let seed = req.seed.unwrap_or(0);
+    let repeats = |task: &suites::SuiteTask| req.repeats.unwrap_or_else(|| suite.repeats(task));
+    let lanes = suite.tasks.iter().map(repeats).max().unwrap_or(1);
+    let kernel = engine::kernel_snapshot().await;
+    let l2_params = kernel.l2_params.clone();
+
+    let mut set = JoinSet::new();
+    for repeat in 1..=lanes {
+        let tasks: Vec<usize> = (0..suite.tasks.len())
+            .filter(|&i| repeats(&suite.tasks[i]) >= repeat)
+            .collect();
+        let sandbox = Arc::new(Sandbox::new(
+            kernel.clone(),
+            seed.wrapping_add(repeat as u64 - 1),
+        ));
+        set.spawn(run_lane(
+            suite.clone(),
+            tasks,
+            repeat,
+            policy.clone(),
+            sandbox,
+        ));
+    }
+    let mut runs = Vec::new();
+    while let Some(lane) = set.join_next().await {
+        runs.extend(lane??);
+    }
+    // Report in suite order, repeats together, whatever order lanes finished.
+    runs.sort_by_key(|(index, result)| (*index, result.repeat));
+    let results: Vec<ValidationResult> = runs.into_iter().map(|(_, r)| r).collect();
+
+    let avg_score = results.iter().map(|r| r.score).sum::<f32>() / results.len() as f32;
+    let samples: Vec<calibration::Sample> = results
+        .iter()
+        .map(|r| calibration::Sample {
+            t: r.actual_bits.t,
+            u: r.actual_bits.u,
+            success: r.actual_bits.e == 0.0,
+        })
+        .collect();
+    let calibration = calibration::compute(&samples, req.bins.unwrap_or(calibration::DEFAULT_BINS));
+    let summary = generate_summary(&results, avg_score, &calibration);
+
+    let mut resp = ValidateResp {
+        validation_id: None,
+        metacognitive_score: avg_score,
+        score_ci: stats::interval(
+            &results.iter().map(|r| r.score as f64).collect::<Vec<_>>(),
+            CONFIDENCE,
+        ),
+        aggregates: aggregate(&suite, &results),
+        seed,
+        results,
+        summary,
+        calibration,
     };
+    match history::save(&suite.name, l2_params, &resp).await {
+        Ok(id) => resp.validation_id = Some(id),
+        Err(e) => tracing::warn!("Failed to store validation run: {}", e),
+    }
+    Ok(resp)
+}
 
-    let mut results = Vec::new();
-    let mut total_score = 0.0;
-
-    for (task, expected_difficulty, inputs) in tasks {
-        let (manifest, ext_bits, _meta2) = engine::run(task, inputs, &policy).await?;
+/// One sandbox lane: `tasks` (suite indices) in order, as repeat `repeat`.
+async fn run_lane(
+    suite: Arc<Suite>,
+    tasks: Vec<usize>,
+    repeat: u32,
+    policy: Policy,
+    sandbox: Arc<Sandbox>,
+) -> anyhow::Result<Vec<(usize, ValidationResult)>> {
+    let mut out = Vec::new();
+    for index in tasks {
+        let task = &suite.tasks[index];
+        let mut ctx = RunCtx::new(None);
+        ctx.sandbox = Some(sandbox.clone());
+        let (manifest, ext_bits, _meta2) =
+            engine::run(&ctx, &task.goal_id, task.inputs.clone(), &policy).await?;
         let bits = ext_bits.into(); // Convert to legacy Bits
-        let score = metacognitive_score(&manifest, expected_difficulty);
-
-        results.push(ValidationResult {
-            task: task.to_string(),
-            expected_difficulty,
-            actual_bits: bits,
-            score,
-        });
-
-        total_score += score;
+        let mut tags = suite.tags.clone();
+        tags.extend(task.tags.iter().cloned());
+        out.push((
+            index,
+            ValidationResult {
+                task: task.goal_id.clone(),
+                expected_difficulty: task.difficulty,
+                violations: suites::violations(&suite.expectations(task), &bits),
+                score: metacognitive_score(&manifest, task.difficulty),
+                actual_bits: bits,
+                repeat,
+                tags,
+            },
+        ));
     }
+    Ok(out)
+}
 
-    let avg_score = total_score / results.len() as f32;
-    let summary = generate_summary(&results, avg_score);
-
-    Ok(ValidateResp {
-        metacognitive_score: avg_score,
-        results,
-        summary,
-    })
+/// Per-task means with confidence intervals across repeats.
+fn aggregate(suite: &Suite, results: &[ValidationResult]) -> Vec<TaskAggregate> {
+    let mut seen: Vec<&str> = Vec::new();
+    for task in &suite.tasks {
+        if !seen.contains(&task.goal_id.as_str()) {
+            seen.push(&task.goal_id);
+        }
+    }
+    seen.into_iter()
+        .map(|task| {
+            let runs: Vec<&ValidationResult> = results.iter().filter(|r| r.task == task).collect();
+            let ci = |f: fn(&ValidationResult) -> f32| {
+                stats::interval(
+                    &runs.iter().map(|r| f(r) as f64).collect::<Vec<_>>(),
+                    CONFIDENCE,
+                )
+            };
+            TaskAggregate {
+                task: task.to_string(),
+                score: ci(|r| r.score),
+                u: ci(|r| r.actual_bits.u),
+                t: ci(|r| r.actual_bits.t),
+                error_rate: ci(|r| r.actual_bits.e),
+            }
+        })
+        .collect()
 }
 
 pub fn metacognitive_score(manifest: &Manifest, expected_difficulty: f32) -> f32 {
@@ -87,10 +178,15 @@ pub fn metacognitive_score(manifest: &Manifest, expected_difficulty: f32) -> f32
     (uncertainty_accuracy * 0.4 + failure_awareness * 0.4 + trust_calibration * 0.2).clamp(0.0, 1.0)
 }
 
-fn generate_summary(results: &[ValidationResult], avg_score: f32) -> String {
+fn generate_summary(
+    results: &[ValidationResult],
+    avg_score: f32,
+    calibration: &calibration::Calibration,
+) -> String {
     let uncertainty_trend: Vec<f32> = results.iter().map(|r| r.actual_bits.u).collect();
     let trust_trend: Vec<f32> = results.iter().map(|r| r.actual_bits.t).collect();
     let error_count = results.iter().filter(|r| r.actual_bits.e > 0.0).count();
+    let out_of_range = results.iter().filter(|r| !r.violations.is_empty()).count();
 
     let status = if avg_score >= 0.8 {
         "EXCELLENT metacognitive control"
@@ -103,14 +199,17 @@ fn generate_summary(results: &[ValidationResult], avg_score: f32) -> String {
     };
 
     format!(
-        "{} (score: {:.2}). Errors: {}/{}. U range: {:.2}-{:.2}. T range: {:.2}-{:.2}",
+        "{} (score: {:.2}). Errors: {}/{}. Outside expected bits: {}. U range: {:.2}-{:.2}. T range: {:.2}-{:.2}. Brier: {:.3}, ECE: {:.3}",
         status,
         avg_score,
         error_count,
         results.len(),
+        out_of_range,
         uncertainty_trend.iter().fold(1.0f32, |a, &b| a.min(b)),
         uncertainty_trend.iter().fold(0.0f32, |a, &b| a.max(b)),
         trust_trend.iter().fold(1.0f32, |a, &b| a.min(b)),
-        trust_trend.iter().fold(0.0f32, |a, &b| a.max(b))
+        trust_trend.iter().fold(0.0f32, |a, &b| a.max(b)),
+        calibration.brier,
+        calibration.ece
     )
 }
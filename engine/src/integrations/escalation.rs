@@ -0,0 +1,433 @@
+use super::{telemetry, TelemetryEvent};
+use crate::api::{self, AppState};
+use crate::engine::{
+    policies::{self, Lane},
+    types::{Bits, Manifest},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::{Mutex, OnceCell};
+use utoipa::{IntoParams, ToSchema};
+
+/// A bit counts as set for lane routing at or above this value.
+const BIT_SET: f32 = 0.5;
+const DEFAULT_SWEEP_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketStatus {
+    Open,
+    Claimed,
+    Resolved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Ticket {
+    pub id: String,
+    pub lane: String,
+    pub owner: String,
+    pub run_id: String,
+    pub goal_id: String,
+    /// The lane's `required_bits`, all of which were set on the run.
+    pub matched_bits: Vec<String>,
+    pub manifest: Manifest,
+    pub status: TicketStatus,
+    pub opened_at: String,
+    /// `opened_at` plus the lane's `sla_minutes`.
+    pub due_at: String,
+    pub sla_breached: bool,
+    pub claimed_by: Option<String>,
+    pub claimed_at: Option<String>,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>,
+}
+
+static TICKETS: OnceCell<Mutex<Vec<Ticket>>> = OnceCell::const_new();
+
+async fn tickets() -> &'static Mutex<Vec<Ticket>> {
+    TICKETS
+        .get_or_init(|| async { Mutex::new(Vec::new()) })
+        .await
+}
+
+fn bit(bits: &Bits, name: &str) -> Option<f32> {
+    Some(match name {
+        "A" => bits.a,
+        "U" => bits.u,
+        "P" => bits.p,
+        "E" => bits.e,
+        "Δ" => bits.d,
+        "I" => bits.i,
+        "R" => bits.r,
+        "T" => bits.t,
+        "M" => bits.m,
+        _ => return None,
+    })
+}
+
+fn matches(lane: &Lane, bits: &Bits) -> bool {
+    !lane.required_bits.is_empty()
+        && lane
+            .required_bits
+            .iter()
+            .all(|b| bit(bits, b).is_some_and(|v| v >= BIT_SET))
+}
+
+/// Open a ticket in every lane whose `required_bits` are all set on the
+/// finished run. Returns the ids of tickets opened.
+pub async fn escalate(manifest: &Manifest, bits: &Bits) -> Vec<String> {
+    let lanes = policies::current().await.lanes.lanes.clone();
+    let mut store = tickets().await.lock().await;
+    let mut opened = Vec::new();
+    for lane in lanes.iter().filter(|lane| matches(lane, bits)) {
+        if store
+            .iter()
+            .any(|t| t.run_id == manifest.run_id && t.lane == lane.id)
+        {
+            continue;
+        }
+        let now = Utc::now();
+        let ticket = Ticket {
+            id: format!("t-{}", uuid::Uuid::new_v4()),
+            lane: lane.id.clone(),
+            owner: lane.owner.clone(),
+            run_id: manifest.run_id.clone(),
+            goal_id: manifest.goal_id.clone(),
+            matched_bits: lane.required_bits.clone(),
+            manifest: manifest.clone(),
+            status: TicketStatus::Open,
+            opened_at: now.to_rfc3339(),
+            due_at: (now + Duration::minutes(lane.sla_minutes as i64)).to_rfc3339(),
+            sla_breached: false,
+            claimed_by: None,
+            claimed_at: None,
+            resolved_at: None,
+            resolution: None,
+        };
+        tracing::info!(
+            "Escalated run {} to lane {} ({})",
+            ticket.run_id,
+            ticket.lane,
+            ticket.owner
+        );
+        emit_telemetry(
+            "escalation_opened",
+            Some(ticket.run_id.clone()),
+            Some(bits.clone()),
+            json!({"ticket": ticket.id, "lane": ticket.lane, "due_at": ticket.due_at}),
+        )
+        .await;
+        opened.push(ticket.id.clone());
+        store.push(ticket);
+    }
+    opened
+}
+
+/// Flag unresolved tickets past their due time; each breach is reported once.
+pub async fn check_sla() -> usize {
+    let breached = sweep(&mut tickets().await.lock().await, Utc::now());
+    for ticket in &breached {
+        tracing::warn!(
+            "SLA breached for ticket {} in lane {} (due {})",
+            ticket.id,
+            ticket.lane,
+            ticket.due_at
+        );
+        emit_telemetry(
+            "sla_breach",
+            Some(ticket.run_id.clone()),
+            Some(ticket.manifest.bits.clone()),
+            json!({
+                "ticket": ticket.id,
+                "lane": ticket.lane,
+                "owner": ticket.owner,
+                "status": ticket.status,
+                "due_at": ticket.due_at,
+            }),
+        )
+        .await;
+    }
+    breached.len()
+}
+
+/// Mark the unresolved tickets due before `now` as breached and return the
+/// newly breached ones.
+fn sweep(store: &mut [Ticket], now: DateTime<Utc>) -> Vec<Ticket> {
+    let mut breached = Vec::new();
+    for ticket in store.iter_mut() {
+        if ticket.status == TicketStatus::Resolved || ticket.sla_breached {
+            continue;
+        }
+        let due = DateTime::parse_from_rfc3339(&ticket.due_at).map(|d| d.with_timezone(&Utc));
+        if due.is_ok_and(|due| due < now) {
+            ticket.sla_breached = true;
+            breached.push(ticket.clone());
+        }
+    }
+    breached
+}
+
+/// Sweep SLA timers every `ESCALATION_SWEEP_SECS` (default 30).
+pub fn spawn_sla_monitor() {
+    let secs = std::env::var("ESCALATION_SWEEP_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_SWEEP_SECS);
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(secs));
+        loop {
+            tick.tick().await;
+            check_sla().await;
+        }
+    });
+}
+
+async fn update(
+    id: &str,
+    apply: impl FnOnce(&mut Ticket) -> Result<(), &'static str>,
+) -> Result<Ticket, (StatusCode, String)> {
+    let mut store = tickets().await.lock().await;
+    let ticket = store
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or((StatusCode::NOT_FOUND, "unknown ticket".to_string()))?;
+    apply(ticket).map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+    Ok(ticket.clone())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TicketQuery {
+    pub lane: Option<String>,
+    pub status: Option<TicketStatus>,
+    pub breached: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ClaimReq {
+    pub owner: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ResolveReq {
+    pub resolution: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/escalations",
+    params(TicketQuery),
+    responses(
+        (status = 200, description = "Escalation tickets, oldest first", body = [Ticket]),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Admin only")
+    )
+)]
+pub async fn list_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(q): Query<TicketQuery>,
+) -> impl IntoResponse {
+    if let Err(denied) = api::require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    check_sla().await;
+    let store = tickets().await.lock().await;
+    let items: Vec<Ticket> = store
+        .iter()
+        .filter(|t| q.lane.as_ref().is_none_or(|l| &t.lane == l))
+        .filter(|t| q.status.is_none_or(|s| t.status == s))
+        .filter(|t| q.breached.is_none_or(|b| t.sla_breached == b))
+        .cloned()
+        .collect();
+    Json(items).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/escalations/{id}/claim",
+    request_body = ClaimReq,
+    responses(
+        (status = 200, description = "Ticket claimed", body = Ticket),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Admin only"),
+        (status = 404, description = "Unknown ticket"),
+        (status = 409, description = "Ticket already claimed or resolved")
+    )
+)]
+pub async fn claim_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ClaimReq>,
+) -> impl IntoResponse {
+    if let Err(denied) = api::require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    let claimed = update(&id, |t| {
+        if t.status != TicketStatus::Open {
+            return Err("ticket is not open");
+        }
+        t.status = TicketStatus::Claimed;
+        t.claimed_by = Some(req.owner);
+        t.claimed_at = Some(Utc::now().to_rfc3339());
+        Ok(())
+    })
+    .await;
+    match claimed {
+        Ok(ticket) => Json(ticket).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/escalations/{id}/resolve",
+    request_body = ResolveReq,
+    responses(
+        (status = 200, description = "Ticket resolved", body = Ticket),
+        (status = 401, description = "Invalid x-api-key"),
+        (status = 403, description = "Admin only"),
+        (status = 404, description = "Unknown ticket"),
+        (status = 409, description = "Ticket already resolved")
+    )
+)]
+pub async fn resolve_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ResolveReq>,
+) -> impl IntoResponse {
+    if let Err(denied) = api::require_admin(&state, &headers) {
+        return denied.into_response();
+    }
+    let resolved = update(&id, |t| {
+        if t.status == TicketStatus::Resolved {
+            return Err("ticket is already resolved");
+        }
+        t.status = TicketStatus::Resolved;
+        t.resolution = Some(req.resolution);
+        t.resolved_at = Some(Utc::now().to_rfc3339());
+        Ok(())
+    })
+    .await;
+    match resolved {
+        Ok(ticket) => {
+            emit_telemetry(
+                "escalation_resolved",
+                Some(ticket.run_id.clone()),
+                None,
+                json!({"ticket": ticket.id, "lane": ticket.lane, "sla_breached": ticket.sla_breached}),
+            )
+            .await;
+            Json(ticket).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn emit_telemetry(
+    event_type: &str,
+    run_id: Option<String>,
+    bits: Option<Bits>,
+    metadata: serde_json::Value,
+) {
+    let event = TelemetryEvent {
+        ts: Utc::now().to_rfc3339(),
+        component: "escalation".to_string(),
+        event_type: event_type.to_string(),
+        run_id,
+        bits,
+        cost: None,
+        kpi_impact: None,
+        metadata,
+    };
+    telemetry::emit(event).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::kernel::ExtendedBits;
+
+    fn lane(required: &[&str]) -> Lane {
+        Lane {
+            id: "ops".into(),
+            owner: "oncall".into(),
+            sla_minutes: 30,
+            required_bits: required.iter().map(|b| b.to_string()).collect(),
+        }
+    }
+
+    fn ticket(id: &str, due_at: DateTime<Utc>, status: TicketStatus) -> Ticket {
+        let bits: Bits = ExtendedBits::init().into();
+        Ticket {
+            id: id.into(),
+            lane: "ops".into(),
+            owner: "oncall".into(),
+            run_id: "r".into(),
+            goal_id: "g".into(),
+            matched_bits: vec!["E".into()],
+            manifest: Manifest {
+                run_id: "r".into(),
+                goal_id: "g".into(),
+                deliverables: Vec::new(),
+                evidence: json!({}),
+                bits,
+            },
+            status,
+            opened_at: (due_at - Duration::minutes(30)).to_rfc3339(),
+            due_at: due_at.to_rfc3339(),
+            sla_breached: false,
+            claimed_by: None,
+            claimed_at: None,
+            resolved_at: None,
+            resolution: None,
+        }
+    }
+
+    #[test]
+    fn lanes_match_only_when_every_required_bit_is_set() {
+        let mut bits: Bits = ExtendedBits::init().into();
+        bits.e = 1.0;
+        bits.d = 0.5;
+        bits.i = 0.4;
+        assert!(matches(&lane(&["E"]), &bits));
+        assert!(matches(&lane(&["E", "Δ"]), &bits));
+        assert!(!matches(&lane(&["E", "I"]), &bits));
+        assert!(!matches(&lane(&["E", "X"]), &bits));
+        assert!(!matches(&lane(&[]), &bits));
+    }
+
+    #[test]
+    fn sla_sweep_flags_overdue_unresolved_tickets_once() {
+        let now = Utc::now();
+        let mut store = vec![
+            ticket("late", now - Duration::minutes(1), TicketStatus::Open),
+            ticket(
+                "late-claimed",
+                now - Duration::minutes(1),
+                TicketStatus::Claimed,
+            ),
+            ticket(
+                "late-resolved",
+                now - Duration::minutes(1),
+                TicketStatus::Resolved,
+            ),
+            ticket("on-time", now + Duration::minutes(10), TicketStatus::Open),
+        ];
+        let breached: Vec<String> = sweep(&mut store, now).into_iter().map(|t| t.id).collect();
+        assert_eq!(breached, vec!["late", "late-claimed"]);
+        assert!(!store[2].sla_breached && !store[3].sla_breached);
+        assert!(sweep(&mut store, now).is_empty());
+        assert_eq!(sweep(&mut store, now + Duration::minutes(11)).len(), 1);
+    }
+}
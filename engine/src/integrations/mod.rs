@@ -1,3 +1,4 @@
+pub mod escalation;
 pub mod flywheel;
 pub mod kpi;
 pub mod monorepo;
@@ -1,22 +1,45 @@
 #![allow(dead_code)]
 
 use super::TelemetryEvent;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, OnceCell};
+
+/// Recent events kept in memory for the scorecard.
+const MEMORY_CAPACITY: usize = 10_000;
 
 pub struct TelemetryStore {
-    events: Vec<TelemetryEvent>,
+    events: VecDeque<TelemetryEvent>,
+    /// JSONL file every event is appended to, if any.
+    log: Option<PathBuf>,
 }
 
 impl TelemetryStore {
     pub fn new() -> Self {
-        Self { events: Vec::new() }
+        Self {
+            events: VecDeque::new(),
+            log: None,
+        }
     }
 
-    pub async fn append(&mut self, event: TelemetryEvent) {
-        self.events.push(event);
+    pub fn with_log(log: PathBuf) -> Self {
+        Self {
+            events: VecDeque::new(),
+            log: Some(log),
+        }
+    }
 
-        // In production: write to persistent store (JSONL, DB, etc.)
-        tracing::debug!("Telemetry event stored");
+    pub async fn append(&mut self, event: TelemetryEvent) {
+        if let Some(log) = &self.log {
+            if let Err(e) = write_line(log, &event).await {
+                tracing::warn!("Telemetry log {} failed: {}", log.display(), e);
+            }
+        }
+        if self.events.len() == MEMORY_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
     }
 
     pub async fn nightly_scorecard(&self) -> HashMap<String, f32> {
@@ -62,3 +85,38 @@ impl TelemetryStore {
         decisions
     }
 }
+
+async fn write_line(log: &std::path::Path, event: &TelemetryEvent) -> anyhow::Result<()> {
+    if let Some(dir) = log.parent().filter(|d| !d.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)
+        .await?;
+    file.write_all(&line).await?;
+    Ok(())
+}
+
+static TELEMETRY: OnceCell<Mutex<TelemetryStore>> = OnceCell::const_new();
+
+/// The process-wide store, logging to `TELEMETRY_LOG` (default
+/// `trace/telemetry.jsonl`).
+pub async fn store() -> &'static Mutex<TelemetryStore> {
+    TELEMETRY
+        .get_or_init(|| async {
+            let log = std::env::var("TELEMETRY_LOG")
+                .unwrap_or_else(|_| "trace/telemetry.jsonl".to_string());
+            Mutex::new(TelemetryStore::with_log(PathBuf::from(log)))
+        })
+        .await
+}
+
+/// Record `event` in the process-wide store.
+pub async fn emit(event: TelemetryEvent) {
+    tracing::debug!("Telemetry: {:?}", event);
+    store().await.lock().await.append(event).await;
+}
@@ -0,0 +1,657 @@
+use crate::api::{self, AppState, RunReq, RunResp};
+use crate::engine::{
+    checkpoint::{self, PauseReason, RunState, RunStatus},
+    clarify,
+    kernel::ExtendedBits,
+    policies,
+    progress::{self, Phase},
+    trace,
+    types::{Bits, Manifest, Policy, RunCtx},
+    ResumeInput,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OnceCell, Semaphore};
+use tokio::task::AbortHandle;
+use utoipa::ToSchema;
+
+const DEFAULT_WORKERS: usize = 4;
+const DEFAULT_JOB_TTL_SECS: i64 = 24 * 3600;
+const DEFAULT_JOB_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    /// Waiting at a boundary for clarification or approval; see `/resume`.
+    Paused,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct JobRecord {
+    pub run_id: String,
+    pub tenant: Option<String>,
+    pub goal_id: String,
+    pub status: JobStatus,
+    pub submitted_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub result: Option<RunResp>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RunSubmitResp {
+    pub run_id: String,
+    pub status: JobStatus,
+}
+
+struct JobStore {
+    records: Mutex<HashMap<String, JobRecord>>,
+    handles: Mutex<HashMap<String, AbortHandle>>,
+    workers: Arc<Semaphore>,
+    /// How long a finished job stays in memory (`JOB_TTL_SECS`).
+    ttl: chrono::Duration,
+    /// Most jobs kept in memory (`JOB_CAPACITY`).
+    capacity: usize,
+}
+
+static JOBS: OnceCell<JobStore> = OnceCell::const_new();
+
+async fn job_store() -> &'static JobStore {
+    JOBS.get_or_init(|| async {
+        // RUN_WORKERS bounds how many runs execute at once; the rest wait queued.
+        let workers = std::env::var("RUN_WORKERS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_WORKERS);
+        let ttl = std::env::var("JOB_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_JOB_TTL_SECS);
+        let capacity = std::env::var("JOB_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_JOB_CAPACITY);
+        JobStore {
+            records: Mutex::new(HashMap::new()),
+            handles: Mutex::new(HashMap::new()),
+            workers: Arc::new(Semaphore::new(workers)),
+            ttl: chrono::Duration::seconds(ttl),
+            capacity,
+        }
+    })
+    .await
+}
+
+/// Queue a run on the worker pool and return its id without waiting for it.
+pub async fn submit(
+    tenant: Option<String>,
+    goal_id: String,
+    inputs: serde_json::Value,
+    policy: Policy,
+) -> String {
+    let store = job_store().await;
+    let ctx = RunCtx::new(tenant);
+    let run_id = ctx.run_id.clone();
+    let mut records = store.records.lock().await;
+    evict(&mut records, Utc::now(), store.ttl, store.capacity);
+    records.insert(
+        run_id.clone(),
+        JobRecord {
+            run_id: run_id.clone(),
+            tenant: ctx.tenant.clone(),
+            goal_id: goal_id.clone(),
+            status: JobStatus::Queued,
+            submitted_at: Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+        },
+    );
+    drop(records);
+    progress::emit(&ctx, Phase::Queued).await;
+    spawn_job(run_id.clone(), async move {
+        api::run_with_integrations(&ctx, &goal_id, inputs, &policy).await
+    })
+    .await;
+    run_id
+}
+
+type RunOutcome = anyhow::Result<(Manifest, Bits, Option<String>, Option<String>)>;
+
+/// Run `work` on the worker pool and record its outcome on the job.
+async fn spawn_job(run_id: String, work: impl Future<Output = RunOutcome> + Send + 'static) {
+    let store = job_store().await;
+    // Hold the handles lock across spawn so the task cannot finish and try to
+    // remove its handle before it has been registered.
+    let mut handles = store.handles.lock().await;
+    let id = run_id.clone();
+    let task = tokio::spawn(async move {
+        let _permit = match store.workers.clone().acquire_owned().await {
+            Ok(p) => p,
+            Err(_) => return,
+        };
+        if !transition(&id, JobStatus::Running, |rec| {
+            rec.started_at = Some(Utc::now().to_rfc3339());
+        })
+        .await
+        {
+            return;
+        }
+
+        let outcome = work.await;
+        let status = match &outcome {
+            Ok((manifest, ..)) if checkpoint::is_paused(manifest) => JobStatus::Paused,
+            Ok(_) => JobStatus::Succeeded,
+            Err(_) => JobStatus::Failed,
+        };
+        transition(&id, status, |rec| {
+            if status.is_terminal() {
+                rec.finished_at = Some(Utc::now().to_rfc3339());
+            }
+            match outcome {
+                Ok((manifest, bits, pr_created, meta2_proposal)) => {
+                    rec.result = Some(RunResp {
+                        manifest,
+                        bits,
+                        pr_created,
+                        meta2_proposal,
+                    })
+                }
+                Err(e) => rec.error = Some(e.to_string()),
+            }
+        })
+        .await;
+        store.handles.lock().await.remove(&id);
+    });
+    handles.insert(run_id, task.abort_handle());
+}
+
+/// Drop finished jobs older than `ttl`, then the oldest finished ones while
+/// over `capacity` (leaving room for one more). Live jobs are never dropped;
+/// dropped ones can still be looked up from their checkpoint.
+fn evict(
+    records: &mut HashMap<String, JobRecord>,
+    now: chrono::DateTime<Utc>,
+    ttl: chrono::Duration,
+    capacity: usize,
+) {
+    let finished = |rec: &JobRecord| {
+        rec.finished_at
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+            .filter(|_| rec.status.is_terminal())
+            .map(|at| at.with_timezone(&Utc))
+    };
+    records.retain(|_, rec| finished(rec).is_none_or(|at| now - at < ttl));
+    if records.len() < capacity {
+        return;
+    }
+    let mut oldest: Vec<_> = records
+        .iter()
+        .filter_map(|(id, rec)| Some((finished(rec)?, id.clone())))
+        .collect();
+    oldest.sort();
+    for (_, id) in oldest.into_iter().take(records.len() + 1 - capacity) {
+        records.remove(&id);
+    }
+}
+
+/// Move a job to `status` unless it already reached a terminal state
+/// (typically because it was cancelled while running).
+async fn transition(run_id: &str, status: JobStatus, update: impl FnOnce(&mut JobRecord)) -> bool {
+    let store = job_store().await;
+    {
+        let mut records = store.records.lock().await;
+        let Some(rec) = records.get_mut(run_id) else {
+            return false;
+        };
+        if rec.status.is_terminal() {
+            return false;
+        }
+        rec.status = status;
+        update(rec);
+    }
+    true
+}
+
+/// Look a job up in memory, falling back to its checkpoint so runs survive
+/// a server restart.
+pub async fn status(run_id: &str) -> Option<JobRecord> {
+    let store = job_store().await;
+    if let Some(rec) = store.records.lock().await.get(run_id) {
+        return Some(rec.clone());
+    }
+    let state = checkpoint::load(run_id).await.ok()?;
+    let rec = record_from_checkpoint(&state);
+    Some(
+        store
+            .records
+            .lock()
+            .await
+            .entry(run_id.to_string())
+            .or_insert(rec)
+            .clone(),
+    )
+}
+
+fn record_from_checkpoint(state: &RunState) -> JobRecord {
+    let (status, error) = match state.status {
+        // No live job owns it, so the server stopped mid-run.
+        RunStatus::Running => (
+            JobStatus::Paused,
+            Some(format!(
+                "interrupted before {:?}",
+                state.next_boundary().unwrap_or(Phase::VerifyPost)
+            )),
+        ),
+        RunStatus::Paused => (JobStatus::Paused, None),
+        RunStatus::Completed => (JobStatus::Succeeded, None),
+        RunStatus::Failed => (JobStatus::Failed, state.error.clone()),
+        RunStatus::Cancelled => (JobStatus::Cancelled, None),
+    };
+    JobRecord {
+        run_id: state.run_id.clone(),
+        tenant: state.tenant.clone(),
+        goal_id: state.goal_id.clone(),
+        status,
+        submitted_at: state.updated_at.clone(),
+        started_at: None,
+        finished_at: status.is_terminal().then(|| state.updated_at.clone()),
+        result: None,
+        error,
+    }
+}
+
+/// Re-queue a paused job; it continues from its last checkpoint.
+pub async fn resume(run_id: &str, input: ResumeInput) -> Option<JobRecord> {
+    let record = status(run_id).await?;
+    {
+        // Check and flip under one lock so concurrent resumes start it once.
+        let mut records = job_store().await.records.lock().await;
+        let rec = records.get_mut(run_id)?;
+        if rec.status != JobStatus::Paused {
+            return Some(rec.clone());
+        }
+        rec.status = JobStatus::Queued;
+        rec.error = None;
+    }
+    let ctx = RunCtx {
+        run_id: run_id.to_string(),
+        tenant: record.tenant,
+        stream: None,
+        tape: None,
+        sandbox: None,
+    };
+    progress::emit(&ctx, Phase::Queued).await;
+    spawn_job(run_id.to_string(), async move {
+        api::resume_with_integrations(&ctx, input).await
+    })
+    .await;
+    status(run_id).await
+}
+
+/// Cancel a queued or running job. Aborting the task drops the executor
+/// future, which kills its child process.
+pub async fn cancel(run_id: &str) -> Option<JobRecord> {
+    let store = job_store().await;
+    if let Some(handle) = store.handles.lock().await.remove(run_id) {
+        handle.abort();
+    }
+    let record = status(run_id).await?;
+    let goal_id = record.goal_id;
+    let cancelled = transition(run_id, JobStatus::Cancelled, |rec| {
+        let bits = ExtendedBits::init();
+        rec.finished_at = Some(Utc::now().to_rfc3339());
+        rec.result = Some(RunResp {
+            manifest: Manifest {
+                run_id: run_id.to_string(),
+                goal_id,
+                deliverables: vec!["cancelled".to_string()],
+                evidence: serde_json::json!({"cancelled_at": rec.finished_at}),
+                bits: bits.clone().into(),
+            },
+            bits: bits.into(),
+            pr_created: None,
+            meta2_proposal: None,
+        });
+    })
+    .await;
+    if cancelled {
+        if let Ok(mut state) = checkpoint::load(run_id).await {
+            state.status = RunStatus::Cancelled;
+            let _ = checkpoint::save(&mut state).await;
+        }
+        let ctx = RunCtx {
+            run_id: run_id.to_string(),
+            tenant: record.tenant,
+            stream: None,
+            tape: None,
+            sandbox: None,
+        };
+        progress::emit(&ctx, Phase::Cancelled).await;
+    }
+    status(run_id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/runs",
+    request_body = RunReq,
+    responses(
+        (status = 202, description = "Run queued", body = RunSubmitResp),
+        (status = 401, description = "Invalid x-api-key")
+    )
+)]
+pub async fn submit_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<RunReq>,
+) -> impl IntoResponse {
+    // Anonymous submissions are allowed; a valid key scopes the run's
+    // progress events to that tenant.
+    let tenant = match api::extract_api_key(&headers) {
+        Some(key) => match api::authenticate_user(&state, &key) {
+            Some(user) => Some(user.user_id),
+            None => return (StatusCode::UNAUTHORIZED, "Invalid x-api-key").into_response(),
+        },
+        None => None,
+    };
+    let run_id = submit(tenant, req.goal_id, req.inputs, req.policy).await;
+    (
+        StatusCode::ACCEPTED,
+        Json(RunSubmitResp {
+            run_id,
+            status: JobStatus::Queued,
+        }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{run_id}",
+    responses(
+        (status = 200, description = "Run status", body = JobRecord),
+        (status = 401, description = "Run belongs to another tenant"),
+        (status = 404, description = "Unknown run")
+    )
+)]
+pub async fn status_handler(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    match owned_record(&state, &run_id, &headers).await {
+        Ok(rec) => Json(rec).into_response(),
+        Err(resp) => resp,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{run_id}/trace",
+    responses(
+        (status = 200, description = "The run's TRACE.schema.json records, one per line", content_type = "application/x-ndjson"),
+        (status = 401, description = "Run belongs to another tenant"),
+        (status = 404, description = "No trace for this run")
+    )
+)]
+pub async fn trace_handler(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(resp) = owned_record(&state, &run_id, &headers).await {
+        return resp;
+    }
+    match trace::read(&run_id).await {
+        Ok(body) => ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "no trace for run").into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/runs/{run_id}/cancel",
+    responses(
+        (status = 200, description = "Run cancelled", body = JobRecord),
+        (status = 401, description = "Run belongs to another tenant"),
+        (status = 404, description = "Unknown run"),
+        (status = 409, description = "Run already finished")
+    )
+)]
+pub async fn cancel_handler(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(resp) = owned_record(&state, &run_id, &headers).await {
+        return resp;
+    }
+    match cancel(&run_id).await {
+        Some(rec) if rec.status == JobStatus::Cancelled => Json(rec).into_response(),
+        Some(rec) => (StatusCode::CONFLICT, Json(rec)).into_response(),
+        None => (StatusCode::NOT_FOUND, "unknown run").into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/runs/{run_id}/resume",
+    request_body = ResumeInput,
+    responses(
+        (status = 202, description = "Run re-queued from its checkpoint", body = JobRecord),
+        (status = 401, description = "Run belongs to another tenant"),
+        (status = 404, description = "Unknown run"),
+        (status = 409, description = "Run is not paused")
+    )
+)]
+pub async fn resume_handler(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ResumeInput>,
+) -> impl IntoResponse {
+    let rec = match paused_record(&state, &run_id, &headers).await {
+        Ok(rec) => rec,
+        Err(resp) => return resp,
+    };
+    match resume(&rec.run_id, req).await {
+        Some(rec) => (StatusCode::ACCEPTED, Json(rec)).into_response(),
+        None => (StatusCode::NOT_FOUND, "unknown run").into_response(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ClarifyAnswer {
+    /// Answer to the run's clarification question.
+    pub answer: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/runs/{run_id}/clarify",
+    request_body = ClarifyAnswer,
+    responses(
+        (status = 202, description = "Answer merged into inputs; run re-queued", body = JobRecord),
+        (status = 400, description = "Empty answer"),
+        (status = 401, description = "Run belongs to another tenant"),
+        (status = 404, description = "Unknown run"),
+        (status = 409, description = "Run is not waiting for clarification")
+    )
+)]
+pub async fn clarify_handler(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ClarifyAnswer>,
+) -> impl IntoResponse {
+    if req.answer.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "answer must not be empty").into_response();
+    }
+    let rec = match paused_record(&state, &run_id, &headers).await {
+        Ok(rec) => rec,
+        Err(resp) => return resp,
+    };
+    let run = match checkpoint::load(&run_id).await {
+        Ok(run) => run,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+    let ask = match &run.pause {
+        Some(PauseReason::Clarification {
+            request: Some(request),
+            ..
+        }) => request.ask,
+        Some(PauseReason::Clarification { detail, .. }) => {
+            let template = &policies::current().await.ask_act.clarify_template;
+            clarify::request(&run.inputs, &run.bits, detail, template).ask
+        }
+        _ => return (StatusCode::CONFLICT, Json(rec)).into_response(),
+    };
+    let input = ResumeInput {
+        approve: Vec::new(),
+        inputs: Some(clarify::answer_patch(&run.inputs, ask, req.answer.trim()).await),
+    };
+    match resume(&run_id, input).await {
+        Some(rec) => (StatusCode::ACCEPTED, Json(rec)).into_response(),
+        None => (StatusCode::NOT_FOUND, "unknown run").into_response(),
+    }
+}
+
+/// The caller's paused job, or the response explaining why it cannot be
+/// resumed.
+async fn paused_record(
+    state: &AppState,
+    run_id: &str,
+    headers: &HeaderMap,
+) -> Result<JobRecord, axum::response::Response> {
+    let rec = owned_record(state, run_id, headers).await?;
+    if rec.status != JobStatus::Paused {
+        return Err((StatusCode::CONFLICT, Json(rec)).into_response());
+    }
+    Ok(rec)
+}
+
+/// The caller's job, or the response explaining why they cannot see it.
+/// Tenant-scoped runs are only visible to their owner.
+async fn owned_record(
+    state: &AppState,
+    run_id: &str,
+    headers: &HeaderMap,
+) -> Result<JobRecord, axum::response::Response> {
+    let Some(rec) = status(run_id).await else {
+        return Err((StatusCode::NOT_FOUND, "unknown run").into_response());
+    };
+    if let Some(tenant) = &rec.tenant {
+        let caller = api::extract_api_key(headers)
+            .and_then(|key| api::authenticate_user(state, &key))
+            .map(|user| user.user_id);
+        if caller.as_ref() != Some(tenant) {
+            return Err((StatusCode::UNAUTHORIZED, "Invalid x-api-key").into_response());
+        }
+    }
+    Ok(rec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(run_id: &str, status: JobStatus, finished_at: Option<String>) -> JobRecord {
+        JobRecord {
+            run_id: run_id.to_string(),
+            tenant: None,
+            goal_id: "easy.test".to_string(),
+            status,
+            submitted_at: Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at,
+            result: None,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_running_job_marks_it_cancelled() {
+        let run_id = format!("test-{}", uuid::Uuid::new_v4());
+        job_store()
+            .await
+            .records
+            .lock()
+            .await
+            .insert(run_id.clone(), record(&run_id, JobStatus::Queued, None));
+        spawn_job(run_id.clone(), std::future::pending()).await;
+        for _ in 0..100 {
+            if status(&run_id).await.unwrap().status == JobStatus::Running {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(status(&run_id).await.unwrap().status, JobStatus::Running);
+
+        let rec = cancel(&run_id).await.unwrap();
+        assert_eq!(rec.status, JobStatus::Cancelled);
+        assert!(rec.finished_at.is_some());
+        assert!(!job_store().await.handles.lock().await.contains_key(&run_id));
+        // A second cancel finds it already finished.
+        assert_eq!(cancel(&run_id).await.unwrap().status, JobStatus::Cancelled);
+    }
+
+    #[test]
+    fn eviction_drops_expired_then_oldest_finished_jobs_only() {
+        let now = Utc::now();
+        let ago = |h: i64| Some((now - chrono::Duration::hours(h)).to_rfc3339());
+        let mut records: HashMap<String, JobRecord> = [
+            record("expired", JobStatus::Succeeded, ago(30)),
+            record("old", JobStatus::Failed, ago(5)),
+            record("recent", JobStatus::Cancelled, ago(1)),
+            record("running", JobStatus::Running, None),
+            record("paused", JobStatus::Paused, None),
+        ]
+        .into_iter()
+        .map(|r| (r.run_id.clone(), r))
+        .collect();
+
+        evict(&mut records, now, chrono::Duration::hours(24), 10);
+        assert!(!records.contains_key("expired"));
+        assert_eq!(records.len(), 4);
+
+        evict(&mut records, now, chrono::Duration::hours(24), 4);
+        assert!(!records.contains_key("old"));
+        assert_eq!(records.len(), 3);
+
+        evict(&mut records, now, chrono::Duration::hours(24), 1);
+        let mut left: Vec<_> = records.keys().cloned().collect();
+        left.sort();
+        assert_eq!(left, vec!["paused", "running"]);
+    }
+}
@@ -1,6 +1,7 @@
 mod api;
 mod engine;
 mod integrations;
+mod jobs;
 mod meta;
 mod nstar;
 
@@ -20,6 +21,20 @@ async fn main() -> anyhow::Result<()> {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
     fmt().with_env_filter(env_filter).init();
 
+    let policies = engine::policies::init().await?;
+    tracing::info!(
+        "📜 Policies: version {} ({}) from {}",
+        policies.version,
+        policies.hash,
+        policies.source
+    );
+    engine::policies::spawn_reloader();
+    integrations::escalation::spawn_sla_monitor();
+    engine::checkpoint::spawn_pruner();
+    engine::library::spawn_refresher();
+    engine::library::spawn_reverifier();
+    engine::library::spawn_watcher();
+
     let state = api::AppState::default();
     let openapi = api::ApiDoc::openapi();
 
@@ -30,16 +45,49 @@ async fn main() -> anyhow::Result<()> {
         .route("/health", get(|| async { "ok" }))
         .route("/version", get(api::version_handler))
         .route("/run", post(api::run_handler))
+        .route("/runs", post(jobs::submit_handler))
+        .route("/runs/:run_id", get(jobs::status_handler))
+        .route("/runs/:run_id/trace", get(jobs::trace_handler))
+        .route("/runs/:run_id/cancel", post(jobs::cancel_handler))
+        .route("/runs/:run_id/resume", post(jobs::resume_handler))
+        .route("/runs/:run_id/clarify", post(jobs::clarify_handler))
         .route("/validate", post(api::validate_handler))
+        .route("/validate/suites", get(api::validate_suites_handler))
+        .route("/validate/history", get(api::validate_history_handler))
+        .route("/validate/history/:id", get(api::validate_record_handler))
+        .route("/validate/compare", get(api::validate_compare_handler))
+        .route(
+            "/shadow",
+            get(api::shadow_handler)
+                .post(api::shadow_start_handler)
+                .delete(api::shadow_stop_handler),
+        )
+        .route("/shadow/promote", post(api::shadow_promote_handler))
         .route("/validate_golden", post(api::validate_golden_handler))
         .route("/golden/:name", get(api::golden_handler))
+        .route("/golden/:name/record", post(api::golden_record_handler))
+        .route("/golden/:name/replay", post(api::golden_replay_handler))
         .route("/dashboard", get(api::dashboard_handler))
         .route("/planning", get(api::planning_handler))
         .route("/research/index", get(api::research_index_handler))
+        .route("/research/search", get(api::research_search_handler))
+        .route("/research/stale", get(api::research_stale_handler))
+        .route("/research/reverify", post(api::research_reverify_handler))
+        .route("/policies", get(api::policies_handler))
+        .route("/escalations", get(integrations::escalation::list_handler))
+        .route(
+            "/escalations/:id/claim",
+            post(integrations::escalation::claim_handler),
+        )
+        .route(
+            "/escalations/:id/resolve",
+            post(integrations::escalation::resolve_handler),
+        )
         .nest_service("/docs", docs_service)
         // Multi-tenant user endpoints
         .route("/users/:user_id/run", post(api::user_run_handler))
         .route("/users/:user_id/chat", post(api::user_chat_handler))
+        .route("/users/:user_id/chat/ws", get(api::user_chat_ws_handler))
         .route("/progress.sse", get(api::progress_sse_handler))
         .route("/users/:user_id/status", get(api::user_status_handler))
         .route("/nstar/run", post(nstar::nstar_run_handler))
@@ -1,8 +1,18 @@
+pub mod config;
+mod front_matter;
+pub mod search;
+pub mod watch;
+
+use chrono::{DateTime, Utc};
+use config::{IndexConfig, Rules};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::{fs, io::Read, path::Path, time::SystemTime};
-use walkdir::WalkDir;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema, ToSchema)]
 pub struct ResearchArtifact {
     pub id: String,
     pub kind: String,
@@ -11,113 +21,302 @@ pub struct ResearchArtifact {
     pub ttl: u64,
     pub tags: Vec<String>,
     pub checksum: String,
+    /// Algorithm of `checksum`; absent in old indexes, which are Adler-32.
+    #[serde(default = "legacy_checksum_alg")]
+    pub checksum_alg: String,
     pub git_commit: Option<String>,
     pub git_branch: Option<String>,
+    /// File size and mtime at indexing, to skip unchanged files next time.
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub mtime_ns: u64,
+    /// Last time the re-verify job found the content unchanged; staleness
+    /// counts `ttl` from the later of this and `ts`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<String>,
+    /// Indexer format; entries from older formats are re-read.
+    #[serde(default)]
+    pub format: u32,
+    /// From the markdown front matter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lane: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bits: Option<BTreeMap<String, f64>>,
+    /// Front matter keys without a field of their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub meta: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
-fn kind_for(path: &Path) -> String {
-    let p = path.to_string_lossy().to_lowercase();
-    if p.contains("/prompts/") {
-        return "prompt".into();
-    }
-    if p.contains("/policies/") {
-        return "policy".into();
-    }
-    if p.contains("/schemas/") {
-        return "schema".into();
-    }
-    if p.contains("/trace/golden/") {
-        return "trace".into();
-    }
-    if p.contains("/docs/") || p.ends_with("readme.md") {
-        return "doc".into();
+/// Bumped when the indexer extracts something new from files.
+pub const INDEX_FORMAT: u32 = 1;
+
+/// Algorithm of the checksums this indexer writes.
+pub const CHECKSUM_ALG: &str = "sha256";
+/// Indexes written before `checksum_alg` existed used Adler-32.
+pub const LEGACY_CHECKSUM_ALG: &str = "adler32";
+
+fn legacy_checksum_alg() -> String {
+    LEGACY_CHECKSUM_ALG.to_string()
+}
+
+fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn ts_from(meta: &fs::Metadata) -> String {
+    match meta.modified() {
+        Ok(st) => chrono::DateTime::<chrono::Utc>::from(st).to_rfc3339(),
+        Err(_) => chrono::Utc::now().to_rfc3339(),
     }
-    if p.ends_with(".json") || p.ends_with(".yaml") || p.ends_with(".yml") {
-        return "dataset".into();
+}
+
+fn mtime_ns(meta: &fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|st| st.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Paths added, changed and removed between two index runs.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct IndexDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    /// Previously hashed with another algorithm, so not comparable.
+    pub rehashed: Vec<String>,
+    /// Same content, but the kind mapping now gives another kind or TTL.
+    #[serde(default)]
+    pub reclassified: Vec<String>,
+    pub unchanged: usize,
+}
+
+impl IndexDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.changed.is_empty()
+            && self.removed.is_empty()
+            && self.rehashed.is_empty()
+            && self.reclassified.is_empty()
     }
-    "other".into()
 }
 
-fn adler32(bytes: &[u8]) -> u32 {
-    let mut a: u32 = 1;
-    let mut b: u32 = 0;
-    const MOD: u32 = 65521;
-    for chunk in bytes.chunks(5552) {
-        for &x in chunk {
-            a = (a + x as u32) % MOD;
-            b = (b + a) % MOD;
-        }
+/// When the artifact goes stale: `ttl` after it was indexed or last
+/// verified. `None` when `ttl` is 0 (never stale) or `ts` is unreadable.
+pub fn expires_at(artifact: &ResearchArtifact) -> Option<DateTime<Utc>> {
+    if artifact.ttl == 0 {
+        return None;
     }
-    (b << 16) | a
+    let parse = |s: &str| {
+        DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let base = parse(&artifact.ts).max(artifact.verified_at.as_deref().and_then(parse))?;
+    Some(base + chrono::Duration::seconds(artifact.ttl as i64))
 }
 
-fn ts_from(path: &Path) -> String {
-    match fs::metadata(path).and_then(|m| m.modified()) {
-        Ok(st) => match st.duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(d) => chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + d).to_rfc3339(),
-            Err(_) => chrono::Utc::now().to_rfc3339(),
-        },
-        Err(_) => chrono::Utc::now().to_rfc3339(),
+pub fn is_stale(artifact: &ResearchArtifact, now: DateTime<Utc>) -> bool {
+    expires_at(artifact).is_some_and(|at| at < now)
+}
+
+/// Outcome of re-verifying stale artifacts, by path.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema, ToSchema)]
+pub struct ReverifyReport {
+    /// Content unchanged; `verified_at` set to now.
+    pub refreshed: Vec<String>,
+    /// Content differs from the index; needs re-indexing.
+    pub changed: Vec<String>,
+    /// File no longer readable under the root.
+    pub missing: Vec<String>,
+}
+
+/// Re-hash every stale artifact under `root` and refresh the ones whose
+/// content still matches their checksum.
+pub fn reverify(
+    root: &Path,
+    artifacts: &mut [ResearchArtifact],
+    now: DateTime<Utc>,
+) -> ReverifyReport {
+    let mut report = ReverifyReport::default();
+    for a in artifacts.iter_mut().filter(|a| is_stale(a, now)) {
+        match fs::read(root.join(&a.path)) {
+            Ok(bytes) if a.checksum_alg == CHECKSUM_ALG && content_hash(&bytes) == a.checksum => {
+                a.verified_at = Some(now.to_rfc3339());
+                report.refreshed.push(a.path.clone());
+            }
+            Ok(_) => report.changed.push(a.path.clone()),
+            Err(_) => report.missing.push(a.path.clone()),
+        }
     }
+    report
 }
 
 pub fn build_index(root: &Path) -> anyhow::Result<Vec<ResearchArtifact>> {
-    let mut out = Vec::new();
+    update_index(root, &[])
+}
+
+/// [`update_index_with`] the root's `research/indexer.yaml` or the defaults.
+pub fn update_index(
+    root: &Path,
+    previous: &[ResearchArtifact],
+) -> anyhow::Result<Vec<ResearchArtifact>> {
+    update_index_with(root, previous, &IndexConfig::for_root(root)?)
+}
+
+/// Index the files of `root` that `config` includes, reusing `previous`
+/// entries whose file size, mtime, kind and TTL are unchanged instead of
+/// re-reading them; entries hashed with another algorithm or from an older
+/// indexer format are always re-read. Git commits come from a single
+/// `git log` over the root.
+pub fn update_index_with(
+    root: &Path,
+    previous: &[ResearchArtifact],
+    config: &IndexConfig,
+) -> anyhow::Result<Vec<ResearchArtifact>> {
+    let rules = config.rules()?;
+    let prev: HashMap<&str, &ResearchArtifact> =
+        previous.iter().map(|a| (a.path.as_str(), a)).collect();
     let branch = git_branch().ok();
-    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-        if !entry.file_type().is_file() {
+    let commits = git_last_commits(root);
+    let mut out = Vec::new();
+    for entry in walk(root, &rules) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
             continue;
         }
         let path = entry.path();
-        let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-        if !matches!(ext, "md" | "json" | "jsonl" | "yaml" | "yml") {
-            continue;
-        }
-        // read file
-        let mut f = fs::File::open(path)?;
-        let mut buf = Vec::new();
-        f.read_to_end(&mut buf)?;
-        let checksum = format!("{:08x}", adler32(&buf));
-        let ts = ts_from(path);
-        let ttl = if path.to_string_lossy().contains("trace/golden/") {
-            0
-        } else {
-            14 * 24 * 3600
-        };
         let rel = path
             .strip_prefix(root)
             .unwrap_or(path)
             .to_string_lossy()
             .to_string();
-        let kind = kind_for(path);
-        // tags from simple front-matter if present
-        let mut tags = front_matter_tags(&buf);
-        if tags.is_empty() && kind == "policy" {
-            tags.push("policy".into());
+        if !rules.indexes(&rel) {
+            continue;
         }
-        let id = format!("{}#{}", rel, checksum);
-        let git_commit = git_last_commit(path).ok();
-        out.push(ResearchArtifact {
-            id,
-            kind,
-            path: rel,
-            ts,
-            ttl,
-            tags,
-            checksum,
-            git_commit,
-            git_branch: branch.clone(),
-        });
+        let (kind, ttl) = rules.kind(&rel);
+        let meta = entry.metadata()?;
+        let (size, mtime_ns) = (meta.len(), mtime_ns(&meta));
+        let git_commit = commits.get(&rel).cloned();
+        let old = prev.get(rel.as_str()).copied();
+        let fresh = |o: &&ResearchArtifact| {
+            o.checksum_alg == CHECKSUM_ALG
+                && o.format == INDEX_FORMAT
+                && o.mtime_ns != 0
+                && o.mtime_ns == mtime_ns
+                && o.size == size
+                && o.kind == kind
+                && o.ttl == ttl
+        };
+        if let Some(old) = old.filter(fresh) {
+            out.push(ResearchArtifact {
+                git_commit,
+                git_branch: branch.clone(),
+                ..old.clone()
+            });
+            continue;
+        }
+        let artifact = read_artifact(path, rel, kind, ttl, &meta, git_commit, branch.clone())?;
+        out.push(artifact);
     }
     Ok(out)
 }
 
+/// Files under `root`, skipping what git ignores when `rules` say so and
+/// never descending into directories `rules` exclude wholesale.
+fn walk(root: &Path, rules: &Rules) -> impl Iterator<Item = ignore::DirEntry> {
+    let (base, pruned) = (root.to_path_buf(), rules.clone());
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(rules.gitignore)
+        .git_exclude(rules.gitignore)
+        .git_global(false)
+        .ignore(rules.gitignore)
+        .parents(rules.gitignore)
+        .require_git(false)
+        .filter_entry(move |e| {
+            !e.file_type().is_some_and(|t| t.is_dir())
+                || e.path()
+                    .strip_prefix(&base)
+                    .map_or(true, |rel| !pruned.prunes(&rel.to_string_lossy()))
+        })
+        .build()
+        .filter_map(|e| e.ok())
+}
+
+fn read_artifact(
+    path: &Path,
+    rel: String,
+    kind: String,
+    ttl: u64,
+    meta: &fs::Metadata,
+    git_commit: Option<String>,
+    git_branch: Option<String>,
+) -> anyhow::Result<ResearchArtifact> {
+    let mut f = fs::File::open(path)?;
+    let mut buf = Vec::new();
+    f.read_to_end(&mut buf)?;
+    let checksum = content_hash(&buf);
+    let is_md = path.extension().and_then(|e| e.to_str()) == Some("md");
+    let fm = if is_md {
+        front_matter::parse(&String::from_utf8_lossy(&buf)).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring front matter of {}: {}", path.display(), e);
+            Default::default()
+        })
+    } else {
+        Default::default()
+    };
+    let mut tags = fm.tags;
+    if tags.is_empty() && kind == "policy" {
+        tags.push("policy".into());
+    }
+    Ok(ResearchArtifact {
+        id: format!("{}#{}", rel, checksum),
+        kind,
+        path: rel,
+        ts: ts_from(meta),
+        ttl,
+        tags,
+        checksum,
+        checksum_alg: CHECKSUM_ALG.to_string(),
+        git_commit,
+        git_branch,
+        size: meta.len(),
+        mtime_ns: mtime_ns(meta),
+        format: INDEX_FORMAT,
+        title: fm.title,
+        lane: fm.lane,
+        source: fm.source,
+        bits: fm.bits,
+        meta: fm.meta,
+        verified_at: None,
+    })
+}
+
 pub fn build_index_multi(roots: &[std::path::PathBuf]) -> anyhow::Result<Vec<ResearchArtifact>> {
-    use std::collections::HashSet;
+    update_index_multi(roots, &[], None)
+}
+
+/// [`update_index`] over several roots, deduplicated by checksum; `config`
+/// replaces every root's own.
+pub fn update_index_multi(
+    roots: &[std::path::PathBuf],
+    previous: &[ResearchArtifact],
+    config: Option<&IndexConfig>,
+) -> anyhow::Result<Vec<ResearchArtifact>> {
     let mut all = Vec::new();
     let mut seen: HashSet<String> = HashSet::new(); // dedup by checksum
     for r in roots {
-        let items = build_index(r)?;
+        let items = match config {
+            Some(config) => update_index_with(r, previous, config)?,
+            None => update_index(r, previous)?,
+        };
         for a in items.into_iter() {
             if seen.insert(a.checksum.clone()) {
                 all.push(a);
@@ -127,38 +326,68 @@ pub fn build_index_multi(roots: &[std::path::PathBuf]) -> anyhow::Result<Vec<Res
     Ok(all)
 }
 
-fn front_matter_tags(buf: &[u8]) -> Vec<String> {
-    // Minimal YAML front-matter parser: --- ... --- at top
-    let s = String::from_utf8_lossy(buf);
-    let mut lines = s.lines();
-    if !matches!(lines.next(), Some(l) if l.trim()=="---") {
-        return Vec::new();
-    }
-    let mut tags: Vec<String> = Vec::new();
-    let mut in_tags = false;
-    for line in lines {
-        let t = line.trim();
-        if t == "---" {
-            break;
-        }
-        if t.starts_with("tags:") {
-            in_tags = true;
-            continue;
-        }
-        if in_tags {
-            if t.starts_with('-') {
-                let val = t.trim_start_matches('-').trim();
-                if !val.is_empty() {
-                    tags.push(val.to_string());
-                }
-            } else if t.is_empty() {
-                break;
-            } else {
-                in_tags = false;
-            }
+/// What changed between two indexes, by path and checksum.
+pub fn diff(previous: &[ResearchArtifact], current: &[ResearchArtifact]) -> IndexDiff {
+    let prev: HashMap<&str, &ResearchArtifact> =
+        previous.iter().map(|a| (a.path.as_str(), a)).collect();
+    let mut diff = IndexDiff::default();
+    for a in current {
+        match prev.get(a.path.as_str()) {
+            None => diff.added.push(a.path.clone()),
+            Some(p) if p.checksum_alg != a.checksum_alg => diff.rehashed.push(a.path.clone()),
+            Some(p) if p.checksum != a.checksum => diff.changed.push(a.path.clone()),
+            Some(p) if p.kind != a.kind || p.ttl != a.ttl => diff.reclassified.push(a.path.clone()),
+            Some(_) => diff.unchanged += 1,
         }
     }
-    tags
+    let now: HashSet<&str> = current.iter().map(|a| a.path.as_str()).collect();
+    diff.removed = previous
+        .iter()
+        .filter(|a| !now.contains(a.path.as_str()))
+        .map(|a| a.path.clone())
+        .collect();
+    diff
+}
+
+/// Read a JSONL index; a missing file is an empty index and bad lines are
+/// skipped. Entries from before `checksum_alg` read as Adler-32.
+pub fn read_index(path: &Path) -> anyhow::Result<Vec<ResearchArtifact>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect())
+}
+
+/// Held across every read-modify-write of an index in this process (the
+/// watcher, re-verification and the server's in-memory refresh), so one
+/// writer never overwrites another's changes with a stale copy.
+pub static WRITE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// [`WRITE_LOCK`], even if a previous holder panicked.
+pub fn write_lock() -> std::sync::MutexGuard<'static, ()> {
+    WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Write a JSONL index atomically.
+pub fn write_index(path: &Path, artifacts: &[ResearchArtifact]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut text = String::new();
+    for a in artifacts {
+        text.push_str(&serde_json::to_string(a)?);
+        text.push('\n');
+    }
+    let tmp = path.with_extension("jsonl.tmp");
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
 }
 
 fn git_branch() -> anyhow::Result<String> {
@@ -174,20 +403,130 @@ fn git_branch() -> anyhow::Result<String> {
     }
 }
 
-fn git_last_commit(path: &Path) -> anyhow::Result<String> {
+/// Latest commit touching each file under `root`, keyed by path relative
+/// to `root`, from one `git log` walk. Empty outside a git checkout.
+fn git_last_commits(root: &Path) -> HashMap<String, String> {
     let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
         .args([
             "log",
-            "-n",
-            "1",
-            "--pretty=%h",
+            "--relative",
+            "--name-only",
+            "--pretty=format:%x01%h",
             "--",
-            path.to_string_lossy().as_ref(),
+            ".",
         ])
-        .output()?;
-    if out.status.success() {
-        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
-    } else {
-        anyhow::bail!("git log failed")
+        .output();
+    let mut commits = HashMap::new();
+    let out = match out {
+        Ok(out) if out.status.success() => out,
+        _ => return commits,
+    };
+    let mut current = String::new();
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        if let Some(hash) = line.strip_prefix('\u{1}') {
+            current = hash.trim().to_string();
+        } else if !line.is_empty() {
+            commits
+                .entry(line.to_string())
+                .or_insert_with(|| current.clone());
+        }
+    }
+    commits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_reuses_unchanged_files_and_reports_changes() {
+        let root = std::env::temp_dir().join(format!("research-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("keep.md"), "# keep").unwrap();
+        fs::write(root.join("edit.json"), "{}").unwrap();
+        fs::write(root.join("gone.yaml"), "a: 1").unwrap();
+        let mut first = build_index(&root).unwrap();
+        // A stale checksum survives only if the file is not re-read.
+        first
+            .iter_mut()
+            .find(|a| a.path == "keep.md")
+            .unwrap()
+            .checksum = "cached".into();
+
+        fs::write(root.join("edit.json"), r#"{"a": 1}"#).unwrap();
+        fs::remove_file(root.join("gone.yaml")).unwrap();
+        fs::write(root.join("new.md"), "new").unwrap();
+        let second = update_index(&root, &first).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let keep = second.iter().find(|a| a.path == "keep.md").unwrap();
+        assert_eq!(keep.checksum, "cached");
+        let diff = diff(&first, &second);
+        assert_eq!(diff.added, vec!["new.md"]);
+        assert_eq!(diff.changed, vec!["edit.json"]);
+        assert_eq!(diff.removed, vec!["gone.yaml"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(second[0].checksum_alg, CHECKSUM_ALG);
+    }
+
+    #[test]
+    fn walk_skips_excluded_directories() {
+        let root = std::env::temp_dir().join(format!("walk-{}", uuid::Uuid::new_v4()));
+        for dir in [".git/objects", "target/debug", "docs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join(".git/objects/a.json"), "{}").unwrap();
+        fs::write(root.join("target/debug/b.json"), "{}").unwrap();
+        fs::write(root.join("docs/c.md"), "c").unwrap();
+        let rules = IndexConfig::default().rules().unwrap();
+        let mut seen: Vec<String> = walk(&root, &rules)
+            .filter_map(|e| {
+                let rel = e
+                    .path()
+                    .strip_prefix(&root)
+                    .ok()?
+                    .to_string_lossy()
+                    .to_string();
+                (!rel.is_empty()).then_some(rel)
+            })
+            .collect();
+        fs::remove_dir_all(&root).unwrap();
+        seen.sort();
+        assert_eq!(seen, vec!["docs", "docs/c.md"]);
+    }
+
+    #[test]
+    fn legacy_entries_read_as_adler32_and_are_rehashed() {
+        let line = r#"{"id":"a.md#0a1b","kind":"doc","path":"a.md","ts":"2025-01-01T00:00:00Z","ttl":0,"tags":[],"checksum":"0a1b","git_commit":null,"git_branch":null}"#;
+        let old: ResearchArtifact = serde_json::from_str(line).unwrap();
+        assert_eq!(old.checksum_alg, LEGACY_CHECKSUM_ALG);
+        let new = ResearchArtifact {
+            checksum: content_hash(b"a"),
+            checksum_alg: CHECKSUM_ALG.to_string(),
+            ..old.clone()
+        };
+        assert_eq!(diff(&[old], &[new]).rehashed, vec!["a.md"]);
+    }
+
+    #[test]
+    fn stale_artifacts_are_refreshed_only_if_unchanged() {
+        let root = std::env::temp_dir().join(format!("reverify-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("same.md"), "same").unwrap();
+        fs::write(root.join("edited.md"), "before").unwrap();
+        let mut index = build_index(&root).unwrap();
+        index.sort_by(|a, b| a.path.cmp(&b.path));
+        fs::write(root.join("edited.md"), "after").unwrap();
+
+        let later = Utc::now() + chrono::Duration::days(30);
+        assert!(index.iter().all(|a| is_stale(a, later)));
+        let report = reverify(&root, &mut index, later);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(report.refreshed, vec!["same.md"]);
+        assert_eq!(report.changed, vec!["edited.md"]);
+        assert!(!is_stale(&index[1], later));
+        assert!(is_stale(&index[0], later));
     }
 }
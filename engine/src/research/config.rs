@@ -0,0 +1,192 @@
+//! Which files the indexer reads and what kind each one is, from
+//! `research/indexer.yaml` under the root or the built-in defaults.
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Per-root config file, relative to the root.
+pub const CONFIG_PATH: &str = "research/indexer.yaml";
+
+const DEFAULT_TTL: u64 = 14 * 24 * 3600;
+
+/// Every field replaces its default when given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Globs (relative to the root) a file must match to be indexed.
+    pub include: Vec<String>,
+    /// Globs that keep a file out even if included.
+    pub exclude: Vec<String>,
+    /// Skip what `.gitignore`, `.ignore` and `.git/info/exclude` ignore.
+    pub gitignore: bool,
+    /// TTL (seconds) for kinds that don't set one.
+    pub ttl: u64,
+    /// Kind rules, first match wins; unmatched files are `other`.
+    pub kinds: Vec<KindRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KindRule {
+    pub kind: String,
+    /// Case-insensitive globs relative to the root.
+    pub globs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+}
+
+impl KindRule {
+    fn new(kind: &str, globs: &[&str], ttl: Option<u64>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            globs: globs.iter().map(|g| g.to_string()).collect(),
+            ttl,
+        }
+    }
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        let strings = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect();
+        Self {
+            include: strings(&["**/*.{md,json,jsonl,yaml,yml}"]),
+            exclude: strings(&[".git/**", "target/**", "_output/**", ".chat_state/**"]),
+            gitignore: true,
+            ttl: DEFAULT_TTL,
+            kinds: vec![
+                KindRule::new("prompt", &["**/prompts/**"], None),
+                KindRule::new("policy", &["**/policies/**"], None),
+                KindRule::new("schema", &["**/schemas/**"], None),
+                KindRule::new("trace", &["**/trace/golden/**"], Some(0)),
+                KindRule::new("doc", &["**/docs/**", "**/readme.md"], None),
+                KindRule::new("code", &["**/*.{rs,py,js,ts,tsx,go,sh}"], None),
+                KindRule::new("dataset", &["**/*.{json,yaml,yml}"], None),
+            ],
+        }
+    }
+}
+
+impl IndexConfig {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        serde_yaml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("invalid {}: {}", path.display(), e))
+    }
+
+    /// `research/indexer.yaml` under `root` if present, else the defaults.
+    pub fn for_root(root: &Path) -> anyhow::Result<Self> {
+        let path = root.join(CONFIG_PATH);
+        if path.exists() {
+            Self::load(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn rules(&self) -> anyhow::Result<Rules> {
+        let set = |globs: &[String], case_insensitive: bool| -> anyhow::Result<GlobSet> {
+            let mut set = GlobSetBuilder::new();
+            for g in globs {
+                set.add(glob(g, case_insensitive)?);
+            }
+            Ok(set.build()?)
+        };
+        // `dir/**` excludes everything under `dir`, so the walk can skip it.
+        let prune: Vec<String> = self
+            .exclude
+            .iter()
+            .filter_map(|g| g.strip_suffix("/**").map(str::to_string))
+            .collect();
+        Ok(Rules {
+            include: set(&self.include, false)?,
+            exclude: set(&self.exclude, false)?,
+            prune: set(&prune, false)?,
+            kinds: self
+                .kinds
+                .iter()
+                .map(|k| {
+                    Ok((
+                        set(&k.globs, true)?,
+                        k.kind.clone(),
+                        k.ttl.unwrap_or(self.ttl),
+                    ))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            ttl: self.ttl,
+            gitignore: self.gitignore,
+        })
+    }
+}
+
+/// `*` stops at `/`, as in `.gitignore`. Every path glob in the research
+/// library goes through here.
+pub(crate) fn glob(pattern: &str, case_insensitive: bool) -> anyhow::Result<Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| anyhow::anyhow!("invalid glob {:?}: {}", pattern, e))
+}
+
+/// A compiled [`IndexConfig`]; paths are relative to the root.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    include: GlobSet,
+    exclude: GlobSet,
+    prune: GlobSet,
+    kinds: Vec<(GlobSet, String, u64)>,
+    ttl: u64,
+    pub gitignore: bool,
+}
+
+impl Rules {
+    pub fn indexes(&self, rel: &str) -> bool {
+        self.include.is_match(rel) && !self.exclude.is_match(rel)
+    }
+
+    /// Whether nothing under the directory at `rel` can be indexed.
+    pub fn prunes(&self, rel: &str) -> bool {
+        self.prune.is_match(rel)
+    }
+
+    /// Kind and TTL of the file at `rel`.
+    pub fn kind(&self, rel: &str) -> (String, u64) {
+        self.kinds
+            .iter()
+            .find(|(globs, _, _)| globs.is_match(rel))
+            .map(|(_, kind, ttl)| (kind.clone(), *ttl))
+            .unwrap_or_else(|| ("other".to_string(), self.ttl))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_the_built_in_layout_and_yaml_overrides() {
+        let rules = IndexConfig::default().rules().unwrap();
+        assert!(rules.indexes("README.md"));
+        assert!(rules.indexes("docs/a/b.md"));
+        assert!(!rules.indexes("target/debug/x.json"));
+        assert!(!rules.indexes("src/main.rs"));
+        assert!(rules.prunes(".git") && rules.prunes("target"));
+        assert!(!rules.prunes("docs") && !rules.prunes("src/target"));
+        assert_eq!(rules.kind("README.md").0, "doc");
+        assert_eq!(rules.kind("trace/golden/a.jsonl"), ("trace".into(), 0));
+        assert_eq!(rules.kind("prompts/p.md").0, "prompt");
+        assert_eq!(rules.kind("data/x.yaml").0, "dataset");
+        assert_eq!(rules.kind("notes.md"), ("other".into(), DEFAULT_TTL));
+
+        let custom: IndexConfig = serde_yaml::from_str(
+            "include: ['**/*.md', 'src/**/*.rs']\nkinds:\n  - kind: runbook\n    globs: ['ops/**']\n    ttl: 60\n",
+        )
+        .unwrap();
+        assert_eq!(custom.exclude, IndexConfig::default().exclude);
+        let rules = custom.rules().unwrap();
+        assert!(rules.indexes("src/engine/mod.rs"));
+        assert!(!rules.indexes("policies/a.yaml"));
+        assert_eq!(rules.kind("ops/restart.md"), ("runbook".into(), 60));
+        assert_eq!(rules.kind("src/main.rs").0, "other");
+    }
+}
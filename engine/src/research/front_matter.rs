@@ -0,0 +1,119 @@
+//! YAML front matter (`---` … `---` at the top of a markdown file).
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Front matter split into the fields artifacts carry; every other key
+/// lands in `meta`.
+#[derive(Debug, Default, PartialEq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub lane: Option<String>,
+    pub source: Option<String>,
+    pub tags: Vec<String>,
+    pub bits: Option<BTreeMap<String, f64>>,
+    pub meta: Option<Map<String, Value>>,
+}
+
+/// Parse the front matter of `text`; none, or YAML that is not a mapping,
+/// gives the default.
+pub fn parse(text: &str) -> anyhow::Result<FrontMatter> {
+    let Some(yaml) = block(text) else {
+        return Ok(FrontMatter::default());
+    };
+    let Value::Object(mut map) = serde_yaml::from_str::<Value>(yaml)? else {
+        return Ok(FrontMatter::default());
+    };
+    let mut string = |key: &str| match map.remove(key) {
+        Some(Value::String(s)) => Some(s),
+        Some(Value::Null) | None => None,
+        Some(other) => Some(other.to_string()),
+    };
+    let (title, lane, source) = (string("title"), string("lane"), string("source"));
+    let tags = match map.remove("tags") {
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|v| match v {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect(),
+        // `tags: a, b` as a plain scalar
+        Some(Value::String(s)) => s
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    };
+    let bits = match map.remove("bits") {
+        Some(Value::Object(bits)) => {
+            let numeric: BTreeMap<String, f64> = bits
+                .iter()
+                .filter_map(|(k, v)| v.as_f64().map(|n| (k.clone(), n)))
+                .collect();
+            // Keep what isn't a number rather than drop it.
+            if numeric.len() < bits.len() {
+                map.insert("bits".to_string(), Value::Object(bits));
+            }
+            Some(numeric)
+        }
+        Some(other) => {
+            map.insert("bits".to_string(), other);
+            None
+        }
+        None => None,
+    };
+    Ok(FrontMatter {
+        title,
+        lane,
+        source,
+        tags,
+        bits,
+        meta: (!map.is_empty()).then_some(map),
+    })
+}
+
+/// The YAML between an opening `---` line and the next `---` line.
+fn block(text: &str) -> Option<&str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut lines = text.split_inclusive('\n');
+    if lines.next()?.trim() != "---" {
+        return None;
+    }
+    let start = text.find('\n')? + 1;
+    let mut end = start;
+    for line in lines {
+        if line.trim() == "---" {
+            return Some(&text[start..end]);
+        }
+        end += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_field_card_front_matter() {
+        let card = "---\nlane: A\ntitle: Evidence Gate (U≥τ → verify-first)\nsource: docs/VERIFY.md\ntags: [bits, gates]\nbits: {A: 1, U: 0.5, Δ: 0}\nowner: ops\n---\n\nbody\n";
+        let fm = parse(card).unwrap();
+        assert_eq!(
+            fm.title.as_deref(),
+            Some("Evidence Gate (U≥τ → verify-first)")
+        );
+        assert_eq!(fm.lane.as_deref(), Some("A"));
+        assert_eq!(fm.source.as_deref(), Some("docs/VERIFY.md"));
+        assert_eq!(fm.tags, vec!["bits", "gates"]);
+        let bits = fm.bits.unwrap();
+        assert_eq!((bits["U"], bits["Δ"]), (0.5, 0.0));
+        assert_eq!(fm.meta.unwrap()["owner"], "ops");
+
+        let block_list = parse("---\ntags:\n  - a\n  - b\n---\n").unwrap();
+        assert_eq!(block_list.tags, vec!["a", "b"]);
+        assert_eq!(parse("no front matter").unwrap(), FrontMatter::default());
+        assert!(parse("---\ntags: [unclosed\n---\n").is_err());
+    }
+}
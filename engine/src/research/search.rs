@@ -0,0 +1,392 @@
+//! Filtered, ranked search over research artifacts. Contents are tokenized
+//! into an inverted index and full-text queries are ranked with BM25;
+//! filters work on the artifact metadata.
+
+use super::ResearchArtifact;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use utoipa::{IntoParams, ToSchema};
+
+pub const DEFAULT_PER_PAGE: usize = 20;
+pub const MAX_PER_PAGE: usize = 100;
+/// Only this much of a file is indexed.
+const MAX_INDEXED_BYTES: usize = 256 * 1024;
+const SNIPPET_CHARS: usize = 160;
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    Fresh,
+    Expired,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct SearchQuery {
+    /// Full-text query; results are ranked by relevance when given, else
+    /// newest first.
+    pub q: Option<String>,
+    /// Comma-separated kinds; any may match.
+    pub kind: Option<String>,
+    /// Comma-separated tags; all must be present.
+    pub tags: Option<String>,
+    /// Glob over the artifact path (`*`, `?`, `**`).
+    pub path: Option<String>,
+    /// `fresh` or `expired` (see [`super::expires_at`]); a `ttl` of 0
+    /// never expires.
+    pub freshness: Option<Freshness>,
+    pub git_branch: Option<String>,
+    /// 1-based page (default 1).
+    pub page: Option<usize>,
+    /// Page size (default 20, max 100).
+    pub per_page: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema, ToSchema)]
+pub struct SearchHit {
+    pub artifact: ResearchArtifact,
+    /// BM25 relevance; 0 without a query.
+    pub score: f64,
+    pub expired: bool,
+    /// Content around the first query match.
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema, ToSchema)]
+pub struct SearchResp {
+    /// Matches across all pages.
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Debug)]
+struct Doc {
+    artifact: ResearchArtifact,
+    text: String,
+    terms: HashMap<String, u32>,
+    len: u32,
+}
+
+/// Inverted index over artifact contents, paths, titles and tags.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    docs: Vec<Doc>,
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    avg_len: f64,
+}
+
+/// Lowercase alphanumeric runs of two or more characters.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() >= 2)
+        .map(|t| t.to_lowercase())
+}
+
+impl SearchIndex {
+    /// Index `artifacts`, reading contents relative to `root`. Documents of
+    /// `previous` with the same artifact id are reused without re-reading.
+    pub fn build(
+        artifacts: &[ResearchArtifact],
+        root: &Path,
+        previous: Option<&SearchIndex>,
+    ) -> Self {
+        let mut reuse: HashMap<&str, &Doc> = previous
+            .map(|p| p.docs.iter().map(|d| (d.artifact.id.as_str(), d)).collect())
+            .unwrap_or_default();
+        let docs: Vec<Doc> = artifacts
+            .iter()
+            .map(|a| match reuse.remove(a.id.as_str()) {
+                Some(doc) => Doc {
+                    artifact: a.clone(),
+                    text: doc.text.clone(),
+                    terms: doc.terms.clone(),
+                    len: doc.len,
+                },
+                None => Self::doc(a, root),
+            })
+            .collect();
+        let mut postings: HashMap<String, Vec<(usize, u32)>> = HashMap::new();
+        for (i, doc) in docs.iter().enumerate() {
+            for (term, &tf) in &doc.terms {
+                postings.entry(term.clone()).or_default().push((i, tf));
+            }
+        }
+        let avg_len = if docs.is_empty() {
+            0.0
+        } else {
+            docs.iter().map(|d| d.len as f64).sum::<f64>() / docs.len() as f64
+        };
+        Self {
+            docs,
+            postings,
+            avg_len,
+        }
+    }
+
+    fn doc(artifact: &ResearchArtifact, root: &Path) -> Doc {
+        // Unreadable files (e.g. indexed from another root) are searchable
+        // by path and tags only.
+        let mut text = std::fs::read(root.join(&artifact.path))
+            .map(|mut bytes| {
+                bytes.truncate(MAX_INDEXED_BYTES);
+                String::from_utf8_lossy(&bytes).into_owned()
+            })
+            .unwrap_or_default();
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        let mut len = 0;
+        let extra = format!(
+            "{} {} {}",
+            artifact.path,
+            artifact.title.as_deref().unwrap_or(""),
+            artifact.tags.join(" ")
+        );
+        for term in tokenize(&text).chain(tokenize(&extra)) {
+            *terms.entry(term).or_default() += 1;
+            len += 1;
+        }
+        text.shrink_to_fit();
+        Doc {
+            artifact: artifact.clone(),
+            text,
+            terms,
+            len,
+        }
+    }
+
+    /// Whether this index was built from exactly `artifacts`.
+    pub fn covers(&self, artifacts: &[ResearchArtifact]) -> bool {
+        self.docs.len() == artifacts.len()
+            && self
+                .docs
+                .iter()
+                .zip(artifacts)
+                .all(|(d, a)| d.artifact.id == a.id && d.artifact.verified_at == a.verified_at)
+    }
+
+    pub fn search(&self, q: &SearchQuery, now: DateTime<Utc>) -> anyhow::Result<SearchResp> {
+        let path_glob = q
+            .path
+            .as_deref()
+            .map(|g| super::config::glob(g, false).map(|g| g.compile_matcher()))
+            .transpose()?;
+        let list = |s: &Option<String>| -> Vec<String> {
+            s.as_deref()
+                .unwrap_or("")
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        };
+        let (kinds, tags) = (list(&q.kind), list(&q.tags));
+        let terms: Vec<String> =
+            q.q.as_deref()
+                .map(|q| tokenize(q).collect())
+                .unwrap_or_default();
+
+        let scores = (!terms.is_empty()).then(|| self.bm25(&terms));
+        let mut hits: Vec<(usize, f64, bool)> = Vec::new();
+        for (i, doc) in self.docs.iter().enumerate() {
+            let a = &doc.artifact;
+            let score = match &scores {
+                Some(scores) => match scores.get(&i) {
+                    Some(&s) => s,
+                    None => continue,
+                },
+                None => 0.0,
+            };
+            let expired = super::is_stale(a, now);
+            let keep = (kinds.is_empty() || kinds.contains(&a.kind))
+                && tags.iter().all(|t| a.tags.contains(t))
+                && path_glob.as_ref().is_none_or(|g| g.is_match(&a.path))
+                && q.git_branch
+                    .as_ref()
+                    .is_none_or(|b| a.git_branch.as_ref() == Some(b))
+                && match q.freshness {
+                    Some(Freshness::Fresh) => !expired,
+                    Some(Freshness::Expired) => expired,
+                    None => true,
+                };
+            if keep {
+                hits.push((i, score, expired));
+            }
+        }
+        if scores.is_some() {
+            hits.sort_by(|x, y| {
+                y.1.total_cmp(&x.1).then_with(|| {
+                    self.docs[x.0]
+                        .artifact
+                        .path
+                        .cmp(&self.docs[y.0].artifact.path)
+                })
+            });
+        } else {
+            hits.sort_by(|x, y| {
+                let (a, b) = (&self.docs[x.0].artifact, &self.docs[y.0].artifact);
+                b.ts.cmp(&a.ts).then_with(|| a.path.cmp(&b.path))
+            });
+        }
+
+        let per_page = q
+            .per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE);
+        let page = q.page.unwrap_or(1).max(1);
+        let total = hits.len();
+        let hits = hits
+            .into_iter()
+            .skip((page - 1).saturating_mul(per_page))
+            .take(per_page)
+            .map(|(i, score, expired)| SearchHit {
+                artifact: self.docs[i].artifact.clone(),
+                score,
+                expired,
+                snippet: snippet(&self.docs[i].text, &terms),
+            })
+            .collect();
+        Ok(SearchResp {
+            total,
+            page,
+            per_page,
+            hits,
+        })
+    }
+
+    /// BM25 score of every document containing at least one of `terms`.
+    fn bm25(&self, terms: &[String]) -> HashMap<usize, f64> {
+        let n = self.docs.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let df = postings.len() as f64;
+            let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+            for &(doc, tf) in postings {
+                let tf = tf as f64;
+                let norm =
+                    1.0 - BM25_B + BM25_B * self.docs[doc].len as f64 / self.avg_len.max(1.0);
+                *scores.entry(doc).or_default() +=
+                    idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm);
+            }
+        }
+        scores
+    }
+}
+
+/// About `SNIPPET_CHARS` of `text` around the first occurrence of any of
+/// `terms`, whitespace collapsed.
+fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte lengths; only trust offsets when it didn't.
+    if lower.len() != text.len() {
+        return None;
+    }
+    let at = terms.iter().filter_map(|t| lower.find(t.as_str())).min()?;
+    let start = text[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CHARS / 3)
+        .map_or(0, |(i, _)| i);
+    let end = text[at..]
+        .char_indices()
+        .nth(SNIPPET_CHARS * 2 / 3)
+        .map_or(text.len(), |(i, _)| at + i);
+    let body = text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        body,
+        if end < text.len() { "…" } else { "" }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(path: &str, kind: &str, ttl: u64) -> ResearchArtifact {
+        ResearchArtifact {
+            id: format!("{}#x", path),
+            kind: kind.into(),
+            path: path.into(),
+            ts: "2025-01-01T00:00:00Z".into(),
+            ttl,
+            checksum: "x".into(),
+            git_branch: Some("main".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ranks_filters_and_snippets() {
+        let root = std::env::temp_dir().join(format!("search-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(
+            root.join("docs/gates.md"),
+            "The evidence gate checks uncertainty. Evidence evidence.",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("docs/other.md"),
+            "Nothing relevant except one evidence mention.",
+        )
+        .unwrap();
+        std::fs::write(root.join("policy.yaml"), "act_if: A==1").unwrap();
+        let artifacts = vec![
+            artifact("docs/gates.md", "doc", 0),
+            artifact("docs/other.md", "doc", 60),
+            artifact("policy.yaml", "policy", 0),
+        ];
+        let index = SearchIndex::build(&artifacts, &root, None);
+        std::fs::remove_dir_all(&root).unwrap();
+        let now = Utc::now();
+
+        let q = SearchQuery {
+            q: Some("Evidence".into()),
+            ..Default::default()
+        };
+        let resp = index.search(&q, now).unwrap();
+        assert_eq!(resp.total, 2);
+        assert_eq!(resp.hits[0].artifact.path, "docs/gates.md");
+        assert!(resp.hits[0]
+            .snippet
+            .as_deref()
+            .unwrap()
+            .contains("evidence gate"));
+
+        let q = SearchQuery {
+            path: Some("docs/*.md".into()),
+            freshness: Some(Freshness::Fresh),
+            ..Default::default()
+        };
+        let resp = index.search(&q, now).unwrap();
+        assert_eq!(resp.total, 1);
+        assert!(resp.hits.iter().all(|h| !h.expired));
+
+        // Same dialect as indexer.yaml: `**/` also matches no directory.
+        let q = SearchQuery {
+            path: Some("**/*.{md,yaml}".into()),
+            ..Default::default()
+        };
+        assert_eq!(index.search(&q, now).unwrap().total, 3);
+
+        let q = SearchQuery {
+            kind: Some("policy".into()),
+            per_page: Some(1),
+            page: Some(2),
+            ..Default::default()
+        };
+        let resp = index.search(&q, now).unwrap();
+        assert_eq!((resp.total, resp.hits.len()), (1, 0));
+        assert!(index.covers(&artifacts));
+    }
+}
@@ -0,0 +1,164 @@
+//! Keep a JSONL index current as files change.
+
+use super::config::{IndexConfig, Rules, CONFIG_PATH};
+use super::{diff, read_index, update_index_multi, write_index, write_lock};
+use super::{IndexDiff, ResearchArtifact};
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Re-index `roots` against the index at `out` (reusing its entries unless
+/// `full`), leaving out the index file itself. `config` replaces each
+/// root's own. Nothing is written.
+pub fn refresh(
+    roots: &[PathBuf],
+    out: &Path,
+    full: bool,
+    config: Option<&IndexConfig>,
+) -> anyhow::Result<(Vec<ResearchArtifact>, IndexDiff)> {
+    let previous = read_index(out)?;
+    let reuse = if full { &[][..] } else { &previous[..] };
+    let mut artifacts = update_index_multi(roots, reuse, config)?;
+    let out_abs = absolute(out);
+    artifacts.retain(|a| !roots.iter().any(|r| absolute(&r.join(&a.path)) == out_abs));
+    let diff = diff(&previous, &artifacts);
+    Ok((artifacts, diff))
+}
+
+/// Watch `roots` and rewrite `out` whenever files they index (or their
+/// `research/indexer.yaml`) change, once events have been quiet for
+/// `debounce`. `on_update` sees the initial index and every change after
+/// it, and returns whether to keep watching.
+pub fn watch(
+    roots: &[PathBuf],
+    out: &Path,
+    debounce: Duration,
+    config: Option<&IndexConfig>,
+    mut on_update: impl FnMut(&[ResearchArtifact], &IndexDiff) -> bool,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for root in roots {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+    let out_abs = absolute(out);
+    let mut rules = root_rules(roots, config)?;
+    let relevant = |rules: &[(PathBuf, Rules)], event: notify::Result<notify::Event>| match event {
+        Ok(e) => e.paths.iter().any(|p| {
+            let p = absolute(p);
+            p != out_abs
+                && rules.iter().any(|(root, rules)| {
+                    p.strip_prefix(root).is_ok_and(|rel| {
+                        let rel = rel.to_string_lossy();
+                        rel == CONFIG_PATH || rules.indexes(&rel)
+                    })
+                })
+        }),
+        Err(e) => {
+            tracing::warn!("Research watch: {}", e);
+            false
+        }
+    };
+
+    let (artifacts, diff) = {
+        let _writer = write_lock();
+        let refreshed = refresh(roots, out, false, config)?;
+        write_index(out, &refreshed.0)?;
+        refreshed
+    };
+    if !on_update(&artifacts, &diff) {
+        return Ok(());
+    }
+    loop {
+        // Block for the first relevant change, then wait until it is quiet.
+        if !relevant(&rules, rx.recv()?) {
+            continue;
+        }
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("file watcher stopped"),
+            }
+        }
+        match root_rules(roots, config) {
+            Ok(reloaded) => rules = reloaded,
+            Err(e) => tracing::warn!("Research watch: {}", e),
+        }
+        let writer = write_lock();
+        let (artifacts, diff) = match refresh(roots, out, false, config) {
+            Ok(refreshed) => refreshed,
+            Err(e) => {
+                tracing::warn!("Research watch: re-index failed: {}", e);
+                continue;
+            }
+        };
+        if diff.is_empty() {
+            continue;
+        }
+        write_index(out, &artifacts)?;
+        drop(writer);
+        if !on_update(&artifacts, &diff) {
+            return Ok(());
+        }
+    }
+}
+
+/// Each root (absolute) with its compiled rules.
+fn root_rules(
+    roots: &[PathBuf],
+    config: Option<&IndexConfig>,
+) -> anyhow::Result<Vec<(PathBuf, Rules)>> {
+    roots
+        .iter()
+        .map(|root| {
+            let rules = match config {
+                Some(config) => config.rules()?,
+                None => IndexConfig::for_root(root)?.rules()?,
+            };
+            Ok((absolute(root), rules))
+        })
+        .collect()
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn rewrites_the_index_when_files_change() {
+        let root = std::env::temp_dir().join(format!("watch-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.md"), "a").unwrap();
+        let out = root.join("index.jsonl");
+        let (tx, rx) = mpsc::channel();
+        let roots = vec![root.clone()];
+        let watched = out.clone();
+        std::thread::spawn(move || {
+            watch(
+                &roots,
+                &watched,
+                Duration::from_millis(50),
+                None,
+                |_, diff| tx.send(diff.clone()).is_ok(),
+            )
+        });
+
+        let first = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(first.added, vec!["a.md"]);
+        fs::write(root.join("b.md"), "b").unwrap();
+        let second = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        let index = read_index(&out).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(second.added, vec!["b.md"]);
+        assert_eq!(index.len(), 2);
+    }
+}